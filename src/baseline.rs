@@ -0,0 +1,264 @@
+//! Known problems that should not fail a run
+//!
+//! A baseline records the problems present when it was written so that
+//! later runs only fail for problems that are new. Each entry is identified by
+//! the rule, the path (relative to the site root) and a fingerprint of the
+//! value the rule checked. If the value changes the problem is treated as new.
+
+use std::{collections::BTreeSet, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    Diagnostic, Report,
+    error::{Error, Result},
+};
+
+/// Version of the baseline file format written
+const BASELINE_VERSION: u32 = 1;
+
+/// Contents of a baseline file
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Baseline {
+    version: u32,
+    entries: BTreeSet<BaselineEntry>,
+}
+
+/// A problem recorded in a baseline file
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    rule: String,
+    /// Relative to the root of the site and always uses `/` as the separator
+    path: String,
+    /// Hex encoded so the file stays readable
+    fingerprint: String,
+}
+
+impl BaselineEntry {
+    fn new(root_path: &Path, diagnostic: &Diagnostic) -> Self {
+        let path = diagnostic
+            .path()
+            .strip_prefix(root_path)
+            .unwrap_or(diagnostic.path());
+        let path = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        Self {
+            rule: diagnostic.rule().id().to_string(),
+            path,
+            fingerprint: format!("{:016x}", diagnostic.fingerprint()),
+        }
+    }
+
+    /// Id of the rule that found the problem
+    pub fn rule(&self) -> &str {
+        &self.rule
+    }
+
+    /// Path of the file relative to the root of the site
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl std::fmt::Display for BaselineEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", self.rule, self.path)
+    }
+}
+
+impl Baseline {
+    /// Creates a baseline containing all the problems in `report` (Whatever
+    /// their severity)
+    pub(crate) fn from_report(root_path: &Path, report: &Report) -> Self {
+        Self {
+            version: BASELINE_VERSION,
+            entries: report
+                .diagnostics()
+                .iter()
+                .map(|diagnostic| BaselineEntry::new(root_path, diagnostic))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let text =
+            fs::read_to_string(path).map_err(|e| Error::io(path, "Failed to read baseline", e))?;
+        let result: Self =
+            serde_json::from_str(&text).map_err(|source| Error::InvalidBaseline {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        if result.version != BASELINE_VERSION {
+            return Err(Error::UnsupportedBaselineVersion {
+                path: path.to_path_buf(),
+                version: result.version,
+                expected: BASELINE_VERSION,
+            });
+        }
+        Ok(result)
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<()> {
+        let mut text =
+            serde_json::to_string_pretty(self).map_err(|source| Error::InvalidBaseline {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        text.push('\n');
+        fs::write(path, text).map_err(|e| Error::io(path, "Failed to write baseline", e))
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Marks the problems in `report` that are in the baseline and records the
+    /// entries that no longer have a matching problem
+    pub(crate) fn apply(&self, root_path: &Path, report: &mut Report) {
+        let mut unmatched = self.entries.clone();
+        report.mark_baselined(|diagnostic| {
+            let entry = BaselineEntry::new(root_path, diagnostic);
+            unmatched.remove(&entry);
+            self.entries.contains(&entry)
+        });
+        report.set_fixed_baseline_entries(unmatched.into_iter().collect());
+    }
+}
+
+/// Loads (or writes when `update` is set) the baseline at `path` and applies
+/// it to `report`
+pub(crate) fn apply_baseline(
+    path: &Path,
+    update: bool,
+    root_path: &Path,
+    report: &mut Report,
+) -> Result<()> {
+    let baseline = if update {
+        let baseline = Baseline::from_report(root_path, report);
+        baseline.save(path)?;
+        info!(
+            "Baseline written with {} entries to {path:?}",
+            baseline.len()
+        );
+        baseline
+    } else {
+        Baseline::load(path)?
+    };
+    baseline.apply(root_path, report);
+    Ok(())
+}
+
+/// 64 bit FNV-1a hash of `text`
+///
+/// Used instead of the std hasher because the value is stored in the baseline
+/// file and must be stable across builds
+pub(crate) fn fingerprint(text: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    text.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{
+        FileData, Options,
+        rule::{Rule, Severity},
+    };
+
+    fn report(root_path: &Path, descriptions: &[(&str, &str)]) -> Report {
+        let mut result = Report::new();
+        for (path, value) in descriptions {
+            result.add_diagnostic(
+                &root_path.join(path),
+                Rule::DescriptionLength,
+                Severity::Warning,
+                "too short".to_string(),
+                value,
+            );
+        }
+        result
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("baseline.json");
+        let root_path = Path::new("site");
+        let old = report(root_path, &[("content/a.md", "A"), ("content/b.md", "B")]);
+        Baseline::from_report(root_path, &old).save(&path).unwrap();
+        let baseline = Baseline::load(&path).unwrap();
+
+        // Matched after moving the site, b.md was fixed and c.md is new
+        let root_path = Path::new("other/./site");
+        let mut new = report(root_path, &[("content/a.md", "A"), ("content/c.md", "C")]);
+        baseline.apply(root_path, &mut new);
+        let baselined: Vec<_> = new
+            .diagnostics()
+            .iter()
+            .map(|x| (x.path().to_path_buf(), x.is_baselined()))
+            .collect();
+        assert_eq!(
+            baselined,
+            [
+                (PathBuf::from("other/site/content/a.md"), true),
+                (PathBuf::from("other/site/content/c.md"), false),
+            ]
+        );
+        let fixed: Vec<_> = new
+            .fixed_baseline_entries()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(fixed, ["[description-length] content/b.md"]);
+        assert_eq!(new.stats().seo_warnings(), 1);
+
+        // A changed value is a new problem
+        let mut changed = report(root_path, &[("content/a.md", "AA")]);
+        baseline.apply(root_path, &mut changed);
+        assert!(!changed.diagnostics()[0].is_baselined());
+    }
+
+    #[test]
+    fn transparent_not_set_is_baselined() {
+        let root_path = Path::new("site");
+        let path = root_path.join("content/blog/_index.md");
+        let data = FileData::new_from_str(&path, "+++\ntitle = \"Blog\"\n+++\n").unwrap();
+        let options = Options::new(root_path).severity(Rule::TransparentNotSet, Severity::Error);
+        let (_, mut report) = data.extract_section_info(&options).unwrap();
+        assert_eq!(report.diagnostics()[0].rule(), Rule::TransparentNotSet);
+        assert_eq!(report.stats().errors(), 1);
+        assert_eq!(report.exit_code(), 1);
+
+        let baseline = Baseline::from_report(root_path, &report);
+        baseline.apply(root_path, &mut report);
+        assert!(report.diagnostics()[0].is_baselined());
+        assert_eq!(report.stats().errors(), 0);
+        assert_eq!(report.exit_code(), 0);
+    }
+
+    #[test]
+    fn version_must_match() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("baseline.json");
+        fs::write(&path, r#"{"version": 2, "entries": []}"#).unwrap();
+        assert!(matches!(
+            Baseline::load(&path),
+            Err(Error::UnsupportedBaselineVersion { version: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn fingerprint_is_stable() {
+        assert_eq!(fingerprint(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fingerprint("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
@@ -1,11 +1,12 @@
 //! Stores Command Line Interface (cli)  configuration
-use clap::Parser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Default)]
 #[command(
     author,
     version,
     about,
+    args_conflicts_with_subcommands = true,
     long_about = "Performs a few updates and SEO validations as listed below:
     
 Section name: is 'section title' if set or the section folder name
@@ -16,11 +17,62 @@ Updates page's front matter if the page is part of a section
 
 Ensures each section has the `transparent` value set and is boolean
 
+Ensures no two pages or sections end up at the same URL (including `aliases`)
+
+Ensures internal links (`@/path.md#anchor`) point to files and headings that
+exist
+
+Ensures images and files referenced in the content exist (next to the page or
+in `static/`), that images have alt text and are not larger than the limit
+(`max_size_kb` in the `[images]` table of the project config, default 500)
+
+Treats folders with an `index.md` (and no `_index.md`) as page bundles. Only
+`index.md` is checked as a page and other markdown files in the bundle are
+reported as they are not built
+
+Ensures pages in a section with `sort_by` set have the key it sorts by (zola
+leaves them out of the section otherwise) and that weights are unique
+
+Ensures `date` and `updated` are TOML dates, `updated` is not earlier than
+`date`, dates are not in the future (unless the page is a draft) and the pages
+of a series do not mix date-only and date-time dates. With `--set-updated`,
+`updated` is set from the git history (see below)
+
+Drafts (`draft = true`) are treated according to the draft policy (see
+`--drafts`). Published pages that link to drafts and series that only have
+drafts are reported as drafts are not built
+
 SEO Verifications
 1. Ensures that the description in the config.toml is within 140-180 characters
 2. Ensures that the description on the pages is also in the same range    
     
+SUBCOMMANDS
+Without a subcommand the site is checked and updated using the flags below
+(`--check` to only report). The same is available as `check` and `fix`, along
+with `report`, `init`, `explain`, `new` and `lsp`, each with their own options
+(see `<COMMAND> --help`). Flags cannot be combined with a subcommand.
+A site folder with the same name as a subcommand is taken to be the
+subcommand, use `./check` or `-- check` to pass it as the path instead.
+
+RULES
+Each check has an id and a severity (error, warning, info or off) that can be
+set in `zola_abridge_helper.toml` in the root of the site. For example:
+    [rules]
+    description-length = \"info\"
+Use `explain <RULE>` for details. Rule ids: transparent-not-set,
+description-missing, description-not-string, description-length,
+series-not-set, tags-missing-section, invalid-setting,
+duplicate-title, duplicate-description, url-collision, alias-collision,
+broken-link, broken-anchor, missing-asset, image-missing-alt, image-too-large,
+bundle-markdown, sort-key-missing, duplicate-weight, invalid-date,
+updated-before-date, future-date, mixed-date-formats, updated-outdated,
+link-to-draft, draft-only-series
+
 EXCEPTIONS
+A rule can be turned off for a page (or all the pages in a section if set in
+`_index.md`) with a comment in the front matter (the reason is required):
+    # abridge-helper: ignore description-length reason=\"landing page\"
+
 Values for sections default to False meaning that checks are enabled.
 Values set for a page override values set at section level
 - disable_check_series: bool
@@ -29,18 +81,106 @@ Values set for a page override values set at section level
 "
 )]
 /// Stores the configurations acquired via the command line
+///
+/// Without a subcommand the flags below check and update the site (the
+/// original interface, kept so existing scripts and hooks keep working). The
+/// flags cannot be combined with a subcommand.
 pub struct Cli {
-    #[arg(value_name = "PATH", default_value = ".")]
-    /// The root folder to start at
+    #[command(flatten)]
+    /// The site and the files that are processed
+    pub site: SiteArgs,
+
+    /// If set will not modify any files and only report how many files would
+    /// have been changed
     ///
-    /// Usually you want to point this to the root of the zola repo.
-    /// It is required for it to be in a repository with a clean working tree.
-    pub root_path: String,
+    /// Return codes are based on the most severe problem found (See the
+    /// `[rules]` table in `zola_abridge_helper.toml` to set the severity of
+    /// each rule):
+    /// - (0) Nothing found or only "info"
+    /// - (1) Error Occurred (or a rule set to "error" found a problem)
+    /// - (2) Files would have been changed by rules set to "warning"
+    /// - (3) Rules set to "warning" found problems
+    #[arg(long = "check", short = 'c')]
+    pub should_check_only: bool,
+
+    #[command(flatten)]
+    /// How changes are written
+    pub write: WriteArgs,
+
+    #[command(flatten)]
+    /// What is printed at the end of the run
+    pub output: OutputArgs,
+
+    /// Reads a single page from stdin and writes the updated page to stdout
+    ///
+    /// The value is the path the page would have on disk and is used to find
+    /// the section the page belongs to. The file at that path is not read or
+    /// modified. Intended for editors to use as a format on save filter.
+    /// Diagnostics are written to stderr.
+    #[arg(
+        long,
+        value_name = "FILE_PATH",
+        conflicts_with_all = ["should_check_only", "allow_dirty", "backup", "baseline"]
+    )]
+    pub stdin_filepath: Option<String>,
+
+    /// Alternative modes of operation (Checks and updates the content folder
+    /// if not specified)
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Alternative modes of operation
+#[derive(Subcommand, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum Command {
+    /// Reports problems and the files that would be changed without changing
+    /// anything (Same as `--check`)
+    ///
+    /// Return codes are based on the most severe problem found:
+    /// - (0) Nothing found or only "info"
+    /// - (1) Error Occurred (or a rule set to "error" found a problem)
+    /// - (2) Files would have been changed by rules set to "warning"
+    /// - (3) Rules set to "warning" found problems
+    Check(CheckArgs),
+
+    /// Applies the fixes and reports the problems that remain
+    ///
+    /// Requires a clean working tree (or staged changes) unless
+    /// `--allow-dirty` is set. Return codes are the same as for `check`.
+    Fix(FixArgs),
+
+    /// Lists every problem found grouped by file without changing anything
+    ///
+    /// Always returns 0 unless the run could not be completed
+    Report(ReportArgs),
+
+    /// Creates `zola_abridge_helper.toml` in the root of the site with every
+    /// setting commented out at its default
+    Init(InitArgs),
 
+    /// Describes a rule (Lists all the rules if none is given)
+    Explain(ExplainArgs),
+
+    /// Creates a page with the front matter the checks expect
+    ///
+    /// Sets the date to today and the series and tags for the section the page
+    /// is in
+    New(NewArgs),
+
+    /// Runs a language server over stdio
+    ///
+    /// Publishes the same diagnostics as a normal run for open pages and
+    /// sections and offers code actions to apply the fixes
+    Lsp(LspArgs),
+}
+
+/// Settings that control which problems are reported
+#[derive(Args, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Default)]
+pub struct RuleArgs {
     /// When enabled seo errors do not cause the run to fail
     ///
-    /// If there are no other reasons for a run to fail
-    /// and there are SEO warnings then a return code of 3 is used
+    /// The SEO rules are treated as "info" at most (still reported but do not
+    /// affect the return code)
     #[arg(long, short)]
     pub ignore_seo: bool,
 
@@ -51,17 +191,77 @@ pub struct Cli {
     #[arg(long, short = 'd')]
     pub ignore_missing_description: bool,
 
-    /// If set will not modify any files and only report how many files would
-    /// have been changed
+    /// How pages with `draft = true` are treated [default: check]
     ///
-    /// Return codes in this mode:
-    /// - (0) No files would have been changed
-    /// - (1) Error Occurred
-    /// - (2) Files would have been changed
-    /// - (3) SEO warnings present
-    #[arg(long = "check", short = 'c')]
-    pub should_check_only: bool,
+    /// Published pages that link to drafts and series that only contain
+    /// drafts are reported whatever the policy
+    #[arg(long, value_enum, value_name = "POLICY")]
+    pub drafts: Option<DraftPolicy>,
+}
+
+/// The site, the files in it that are processed and the checks run
+#[derive(Args, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Default)]
+pub struct SiteArgs {
+    #[arg(value_name = "PATH", default_value = ".")]
+    /// The root folder to start at
+    ///
+    /// Usually you want to point this to the root of the zola repo.
+    /// It is required for it to be in a repository with a clean working tree.
+    /// Without a subcommand, a folder named like a subcommand (e.g. `check`)
+    /// must be given as `./check` or after `--`.
+    pub root_path: String,
+
+    #[command(flatten)]
+    /// Which problems are reported
+    pub rules: RuleArgs,
+
+    /// Only processes files in the content folder that match the glob (Can be
+    /// used more than once)
+    ///
+    /// Globs are relative to the content folder, for example `blog/**`. Section
+    /// files (`_index.md`) are always included so pages keep their section.
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Does not process files in the content folder that match the glob (Can
+    /// be used more than once)
+    ///
+    /// Files can also be excluded using `.abridgehelperignore` files which use
+    /// the same syntax as `.gitignore`
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Processes files that are ignored by git (By default `.gitignore` is
+    /// respected)
+    #[arg(long)]
+    pub no_gitignore: bool,
+
+    /// Processes hidden files and folders (Names starting with `.`)
+    #[arg(long)]
+    pub hidden: bool,
+
+    /// How symbolic links in the content folder are treated [default: follow]
+    ///
+    /// When followed, a file reached through more than one path is only
+    /// processed the first time and links that lead back to a folder being
+    /// walked are reported as errors
+    #[arg(long, value_enum, value_name = "POLICY")]
+    pub symlinks: Option<SymlinkPolicy>,
+
+    /// Sets `updated` on pages to the date of the last commit that changed
+    /// the body of the page
+    ///
+    /// Commits that only changed the front matter (like the tags and series
+    /// set by this tool) are not counted. Pages whose body has not changed
+    /// since they were added, or not since their `date`, are left as is. The
+    /// date is written in the same format as `date` (date-only or date-time).
+    #[arg(long)]
+    pub set_updated: bool,
+}
 
+/// How changes are written to disk
+#[derive(Args, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Default)]
+pub struct WriteArgs {
     /// Allows changes to be made even if there are dirty files in the vcs.
     /// WARNING: This means that there will be no easy way to undo changes made
     ///
@@ -69,10 +269,173 @@ pub struct Cli {
     /// provided in case users really prefer not needing to stage their files.
     #[arg(long)]
     pub allow_dirty: bool,
+
+    /// Refuses to write a file if any bytes outside of the front matter would
+    /// change
+    ///
+    /// Files are written so that only the front matter changes (line endings,
+    /// BOM and spacing are kept as is). This adds a check before each write to
+    /// guarantee it and reports an error for the file instead of writing.
+    #[arg(long)]
+    pub strict_write: bool,
+
+    /// Keeps a copy of each file before it is changed
+    ///
+    /// The copy is saved next to the file with `.orig` appended to the file
+    /// name (e.g. `post.md.orig`). Recommended when using `--allow-dirty` as
+    /// there is no other way to undo changes in that case. Existing copies are
+    /// overwritten.
+    #[arg(long)]
+    pub backup: bool,
+}
+
+/// What is printed at the end of a run and the baseline it is compared with
+#[derive(Args, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Default)]
+pub struct OutputArgs {
+    /// File with the problems found by the rules that are already known
+    ///
+    /// Only problems that are not in the file affect the return code. Entries
+    /// that no longer match a problem are reported so they can be removed.
+    /// Problems are matched on the rule, the path and the value that was
+    /// checked, so changing a description makes its problem new again.
+    #[arg(long, value_name = "FILE")]
+    pub baseline: Option<String>,
+
+    /// Writes all the problems currently found to the baseline file (replacing
+    /// its contents) instead of comparing against it
+    #[arg(long, requires = "baseline")]
+    pub update_baseline: bool,
+
+    /// Prints the rules turned off by suppression comments along with the
+    /// reason given
+    ///
+    /// A rule can be turned off for a page (or for all the pages in a section
+    /// when placed in `_index.md`) with a comment in the front matter like:
+    /// `# abridge-helper: ignore description-length reason="landing page"`.
+    /// The reason is required.
+    #[arg(long)]
+    pub list_suppressions: bool,
+
+    /// Prints the run statistics as JSON instead of text
+    #[arg(long)]
+    pub stats_json: bool,
+}
+
+/// Options for [`Command::Check`]
+#[derive(Args, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct CheckArgs {
+    #[command(flatten)]
+    /// The site and the files that are processed
+    pub site: SiteArgs,
+
+    #[command(flatten)]
+    /// What is printed at the end of the run
+    pub output: OutputArgs,
+}
+
+/// Options for [`Command::Fix`]
+#[derive(Args, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct FixArgs {
+    #[command(flatten)]
+    /// The site and the files that are processed
+    pub site: SiteArgs,
+
+    #[command(flatten)]
+    /// How changes are written
+    pub write: WriteArgs,
+
+    #[command(flatten)]
+    /// What is printed at the end of the run
+    pub output: OutputArgs,
+}
+
+/// Options for [`Command::Report`]
+#[derive(Args, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct ReportArgs {
+    #[command(flatten)]
+    /// The site and the files that are processed
+    pub site: SiteArgs,
+
+    /// Prints the problems and statistics as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Options for [`Command::Init`]
+#[derive(Args, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct InitArgs {
+    /// The root folder of the zola site
+    #[arg(value_name = "PATH", default_value = ".")]
+    pub root_path: String,
+
+    /// Replaces the project config if it already exists
+    #[arg(long)]
+    pub force: bool,
+}
+
+/// Options for [`Command::Explain`]
+#[derive(Args, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct ExplainArgs {
+    /// Id of the rule (as shown in brackets in the output of a run)
+    #[arg(value_name = "RULE")]
+    pub rule: Option<String>,
+}
+
+/// Options for [`Command::New`]
+#[derive(Args, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct NewArgs {
+    /// Path of the page to create (in the content folder of a zola site)
+    #[arg(value_name = "FILE_PATH")]
+    pub page_path: String,
+
+    /// Title of the page (Defaults to the file name with dashes and
+    /// underscores replaced by spaces)
+    #[arg(long)]
+    pub title: Option<String>,
+
+    /// Creates the page as a draft (`draft = true`)
+    #[arg(long)]
+    pub draft: bool,
+}
+
+/// Options for [`Command::Lsp`]
+#[derive(Args, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct LspArgs {
+    #[command(flatten)]
+    /// Which problems are reported
+    pub rules: RuleArgs,
+}
+
+/// How symbolic links in the content folder are treated
+#[derive(ValueEnum, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug, Default)]
+pub enum SymlinkPolicy {
+    /// Links are followed
+    #[default]
+    Follow,
+    /// Links are not followed (Counted as skipped)
+    Skip,
+    /// Links are reported as errors and not followed
+    Error,
+}
+
+/// How pages with `draft = true` are treated
+#[derive(ValueEnum, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug, Default)]
+pub enum DraftPolicy {
+    /// Drafts are checked and fixed the same as published pages
+    #[default]
+    Check,
+    /// Drafts are not checked or fixed (Counted as skipped)
+    Skip,
+    /// Drafts are checked and fixed but problems found are reported as
+    /// "info" so they do not fail the run
+    NoFail,
+    /// Drafts are fixed but the SEO checks are not run on them
+    FixOnly,
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
 
     #[test]
     fn verify_cli() {
@@ -81,4 +444,85 @@ mod tests {
         use clap::CommandFactory;
         super::Cli::command().debug_assert()
     }
+
+    fn parse(args: &[&str]) -> Cli {
+        Cli::try_parse_from(["zola_abridge_helper"].iter().chain(args)).unwrap()
+    }
+
+    #[test]
+    fn flat_flags() {
+        let cli = parse(&["-c", "--drafts", "skip", "--baseline", "b.json", "site"]);
+        assert_eq!(cli.command, None);
+        assert!(cli.should_check_only);
+        assert_eq!(cli.site.root_path, "site");
+        assert_eq!(cli.site.rules.drafts, Some(DraftPolicy::Skip));
+        assert_eq!(cli.output.baseline.as_deref(), Some("b.json"));
+
+        let cli = parse(&["--allow-dirty", "--backup", "--include", "blog/**"]);
+        assert_eq!(cli.site.root_path, ".");
+        assert!(cli.write.allow_dirty && cli.write.backup);
+        assert_eq!(cli.site.include, ["blog/**"]);
+
+        let cli = parse(&["--stdin-filepath", "content/post.md"]);
+        assert_eq!(cli.stdin_filepath.as_deref(), Some("content/post.md"));
+        assert!(Cli::try_parse_from(["x", "-c", "--stdin-filepath", "a.md"]).is_err());
+    }
+
+    #[test]
+    fn subcommands() {
+        let Some(Command::Check(args)) = parse(&["check", "site", "--stats-json"]).command else {
+            panic!("expected check");
+        };
+        assert_eq!(args.site.root_path, "site");
+        assert!(args.output.stats_json);
+
+        let Some(Command::Fix(args)) = parse(&["fix", "--backup"]).command else {
+            panic!("expected fix");
+        };
+        assert_eq!(args.site.root_path, ".");
+        assert!(args.write.backup);
+
+        let Some(Command::Report(args)) = parse(&["report", "--json"]).command else {
+            panic!("expected report");
+        };
+        assert!(args.json);
+
+        let Some(Command::Init(args)) = parse(&["init", "site", "--force"]).command else {
+            panic!("expected init");
+        };
+        assert_eq!(args.root_path, "site");
+        assert!(args.force);
+
+        let Some(Command::Explain(args)) = parse(&["explain", "broken-link"]).command else {
+            panic!("expected explain");
+        };
+        assert_eq!(args.rule.as_deref(), Some("broken-link"));
+
+        let Some(Command::New(args)) = parse(&["new", "a.md", "--title", "A", "--draft"]).command
+        else {
+            panic!("expected new");
+        };
+        assert_eq!(args.page_path, "a.md");
+        assert_eq!(args.title.as_deref(), Some("A"));
+        assert!(args.draft);
+
+        let Some(Command::Lsp(args)) = parse(&["lsp", "-i"]).command else {
+            panic!("expected lsp");
+        };
+        assert!(args.rules.ignore_seo);
+
+        // Flags of the original interface cannot be combined with a subcommand
+        assert!(Cli::try_parse_from(["x", "check", "--check"]).is_err());
+        assert!(Cli::try_parse_from(["x", "check", "--allow-dirty"]).is_err());
+    }
+
+    #[test]
+    fn site_folder_named_like_a_subcommand() {
+        assert!(matches!(parse(&["check"]).command, Some(Command::Check(_))));
+        for args in [&["./check"][..], &["--", "check"], &["-c", "check"]] {
+            let cli = parse(args);
+            assert_eq!(cli.command, None);
+            assert!(cli.site.root_path.ends_with("check"));
+        }
+    }
 }
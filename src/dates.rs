@@ -0,0 +1,288 @@
+//! Checks of the `date` and `updated` values of pages
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use toml_edit::{Datetime, Item, Offset, Table};
+
+use crate::{processing::is_draft, rule::Rule};
+
+/// Keys of the dates checked
+const DATE_KEYS: [&str; 2] = ["date", "updated"];
+
+/// Problems that can be found with the dates of a page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DateIssue {
+    /// The value is not a TOML date or date-time (Holds the key and value)
+    NotDate { key: &'static str, value: String },
+    /// `updated` is earlier than `date`
+    UpdatedBeforeDate { date: String, updated: String },
+    /// The date is in the future and the page is not a draft
+    Future { key: &'static str, value: String },
+}
+
+/// Whether a date includes the time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum DateFormat {
+    DateOnly,
+    DateTime,
+}
+
+impl std::fmt::Display for DateIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DateIssue::NotDate { key, value } => write!(
+                f,
+                "`{key}` is not a TOML date or date-time (like 2024-01-31 or 2024-01-31T09:00:00Z). Value found: {value}"
+            ),
+            DateIssue::UpdatedBeforeDate { date, updated } => {
+                write!(f, "`updated` ({updated}) is earlier than `date` ({date})")
+            }
+            DateIssue::Future { key, value } => write!(
+                f,
+                "`{key}` ({value}) is in the future and the page is not a draft"
+            ),
+        }
+    }
+}
+
+impl DateIssue {
+    /// Id of the rule that found the issue
+    pub(crate) fn rule(&self) -> Rule {
+        match self {
+            DateIssue::NotDate { .. } => Rule::InvalidDate,
+            DateIssue::UpdatedBeforeDate { .. } => Rule::UpdatedBeforeDate,
+            DateIssue::Future { .. } => Rule::FutureDate,
+        }
+    }
+
+    /// Key the issue is with
+    pub(crate) fn key(&self) -> &'static str {
+        match self {
+            DateIssue::NotDate { key, .. } | DateIssue::Future { key, .. } => key,
+            DateIssue::UpdatedBeforeDate { .. } => "updated",
+        }
+    }
+
+    /// The value that was checked
+    pub(crate) fn value(&self) -> &str {
+        match self {
+            DateIssue::NotDate { value, .. }
+            | DateIssue::Future { value, .. }
+            | DateIssue::UpdatedBeforeDate { updated: value, .. } => value,
+        }
+    }
+}
+
+/// The value of a date key if it is a TOML date or date-time (Strings are not
+/// dates, the same rule is used by all the checks of dates)
+pub(crate) fn as_date(item: &Item) -> Option<&Datetime> {
+    item.as_datetime().filter(|x| x.date.is_some())
+}
+
+/// Finds the problems with `date` and `updated` in the front matter of a page
+/// (Dates after `now` are in the future, see [`now`])
+pub(crate) fn find_date_issues(table: &Table, now: i64) -> Vec<DateIssue> {
+    let mut result = Vec::new();
+    let mut timestamps = [None, None];
+    for (key, timestamp) in DATE_KEYS.into_iter().zip(&mut timestamps) {
+        let Some(item) = table.get(key) else {
+            continue;
+        };
+        match as_date(item).and_then(|x| Some((x, to_timestamp(x)?))) {
+            Some((datetime, value)) => *timestamp = Some((datetime, value)),
+            None => result.push(DateIssue::NotDate {
+                key,
+                value: item.to_string().trim().to_string(),
+            }),
+        }
+    }
+    let [date, updated] = timestamps;
+    if let (Some((date, date_ts)), Some((updated, updated_ts))) = (date, updated)
+        && updated_ts < date_ts
+    {
+        result.push(DateIssue::UpdatedBeforeDate {
+            date: date.to_string(),
+            updated: updated.to_string(),
+        });
+    }
+    if !is_draft(table) {
+        for (key, timestamp) in DATE_KEYS.into_iter().zip(timestamps) {
+            if let Some((datetime, value)) = timestamp
+                && value > now
+            {
+                result.push(DateIssue::Future {
+                    key,
+                    value: datetime.to_string(),
+                });
+            }
+        }
+    }
+    result
+}
+
+/// Whether the `date` of a page includes the time (None if it is not set or
+/// not valid)
+pub(crate) fn date_format(table: &Table) -> Option<DateFormat> {
+    let datetime = as_date(table.get("date")?)?;
+    Some(if datetime.time.is_some() {
+        DateFormat::DateTime
+    } else {
+        DateFormat::DateOnly
+    })
+}
+
+/// The value `updated` should be set to given the time the body of the page
+/// last changed (None if it should be left as is)
+///
+/// The value uses the same format as `date`. Pages whose body has not changed
+/// since their `date` are left as is.
+pub(crate) fn updated_from_history(table: &Table, last_change: Datetime) -> Option<Datetime> {
+    let mut result = last_change;
+    if date_format(table) != Some(DateFormat::DateTime) {
+        result.time = None;
+        result.offset = None;
+    }
+    let date = table.get("date").and_then(|x| x.as_datetime());
+    if let Some(date) = date.and_then(to_timestamp)
+        && to_timestamp(&result)? <= date
+    {
+        return None;
+    }
+    let updated = table.get("updated").and_then(|x| x.as_datetime());
+    (updated != Some(&result)).then_some(result)
+}
+
+/// Seconds since the unix epoch of the current time
+pub(crate) fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |x| i64::try_from(x.as_secs()).unwrap_or(i64::MAX))
+}
+
+/// The current date (UTC)
+pub(crate) fn today() -> Datetime {
+    let (year, month, day) = civil_from_days(now().div_euclid(86_400));
+    Datetime {
+        date: Some(toml_edit::Date {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+        }),
+        time: None,
+        offset: None,
+    }
+}
+
+/// Seconds since the unix epoch (None if there is no date)
+///
+/// Dates without a time are taken as the start of the day and times without an
+/// offset as UTC
+fn to_timestamp(datetime: &Datetime) -> Option<i64> {
+    let date = datetime.date?;
+    let mut result = days_from_civil(
+        i64::from(date.year),
+        i64::from(date.month),
+        i64::from(date.day),
+    ) * 86_400;
+    if let Some(time) = datetime.time {
+        result +=
+            i64::from(time.hour) * 3_600 + i64::from(time.minute) * 60 + i64::from(time.second);
+    }
+    if let Some(Offset::Custom { minutes }) = datetime.offset {
+        result -= i64::from(minutes) * 60;
+    }
+    Some(result)
+}
+
+/// Days since the unix epoch of a date in the proleptic Gregorian calendar
+///
+/// Based on <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The date (year, month, day) of a number of days since the unix epoch
+///
+/// Based on <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use toml_edit::DocumentMut;
+
+    use super::*;
+    use crate::section_info::SortBy;
+
+    #[test]
+    fn date_issues() {
+        let now = days_from_civil(2024, 6, 1) * 86_400;
+        let issues = |text: &str| find_date_issues(&text.parse::<DocumentMut>().unwrap(), now);
+        assert_eq!(
+            issues("date = 2024-01-01\nupdated = 2024-02-01T10:00:00Z"),
+            []
+        );
+        assert_eq!(
+            issues("date = \"2024-01-01\""),
+            [DateIssue::NotDate {
+                key: "date",
+                value: "\"2024-01-01\"".to_string()
+            }]
+        );
+        assert_eq!(
+            issues("date = 2024-01-02\nupdated = 2024-01-01"),
+            [DateIssue::UpdatedBeforeDate {
+                date: "2024-01-02".to_string(),
+                updated: "2024-01-01".to_string()
+            }]
+        );
+        assert_eq!(
+            issues("date = 2024-07-01"),
+            [DateIssue::Future {
+                key: "date",
+                value: "2024-07-01".to_string()
+            }]
+        );
+        assert_eq!(issues("date = 2024-07-01\ndraft = true"), []);
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(civil_from_days(days_from_civil(2024, 2, 29)), (2024, 2, 29));
+    }
+
+    #[test]
+    fn sort_key_and_date_checks_agree() {
+        for (text, is_valid) in [
+            ("date = 2024-01-01", true),
+            ("date = 2024-01-01T10:00:00Z", true),
+            ("date = \"2024-01-01\"", false),
+            ("date = 10:00:00", false),
+            ("date = 20240101", false),
+        ] {
+            let doc = text.parse::<DocumentMut>().unwrap();
+            let has_date_issue = !find_date_issues(&doc, i64::MAX).is_empty();
+            let has_sort_key_issue = SortBy::Date.find_issue(&doc).is_some();
+            assert_eq!(has_date_issue, !is_valid, "{text}");
+            assert_eq!(has_sort_key_issue, !is_valid, "{text}");
+        }
+    }
+}
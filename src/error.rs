@@ -0,0 +1,247 @@
+//! Errors that can occur during a run
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use version_control_clean_check::VCSError;
+
+/// Type alias for Result<T, [`Error`]>
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Enumerates the errors that can occur during a run
+///
+/// Each variant includes the path the error relates to
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Version control was not found or has files that are not allowed
+    #[error(
+        "Failed to find a clean version control system. Files must be at least staged before tool can run or you can opt-out of being able to revert changes. See help for more info.\nPath:{path:?}"
+    )]
+    VcsNotClean {
+        /// Root path of the run
+        path: PathBuf,
+        /// Reason version control was not acceptable
+        source: VCSError,
+    },
+
+    /// The zola config file could not be read
+    #[error("failed to read config at: {path:?}")]
+    ConfigUnreadable {
+        /// Path of the config file
+        path: PathBuf,
+        /// Error encountered reading the file
+        source: io::Error,
+    },
+
+    /// The file did not have TOML front matter (between `+++` lines)
+    #[error("failed to find front matter in: {path:?}")]
+    FrontMatterNotFound {
+        /// Path of the file
+        path: PathBuf,
+    },
+
+    /// The front matter (or config) was not valid TOML
+    #[error("failed to parse TOML in: {path:?}")]
+    InvalidToml {
+        /// Path of the file
+        path: PathBuf,
+        /// Error from the TOML parser
+        source: toml_edit::TomlError,
+    },
+
+    /// An I/O operation on the file system failed
+    #[error("{context}: {path:?}")]
+    Io {
+        /// Path the operation was on
+        path: PathBuf,
+        /// Description of the operation that failed
+        context: &'static str,
+        /// Underlying I/O error
+        source: io::Error,
+    },
+
+    /// The name of the folder a section is in could not be determined
+    #[error("failed to get section folder name for file at: {path:?}")]
+    SectionFolderName {
+        /// Path of the section file (`_index.md`)
+        path: PathBuf,
+    },
+
+    /// A section did not set `transparent` or it was not a boolean
+    #[error("transparent not set or not bool for section in file at: {path:?}")]
+    TransparentNotSet {
+        /// Path of the section file (`_index.md`)
+        path: PathBuf,
+    },
+
+    /// A write was requested for a file that has no changes (Writing it would
+    /// only put back the text that was read)
+    #[error("no change detected, write aborted: {path:?}")]
+    NotChanged {
+        /// Path of the file
+        path: PathBuf,
+    },
+
+    /// A strict write was refused because bytes outside of the front matter
+    /// would have changed
+    #[error("strict write refused, bytes outside of the front matter would change in: {path:?}")]
+    StrictWriteRefused {
+        /// Path of the file
+        path: PathBuf,
+    },
+
+    /// The file read back after writing was not what was expected. The
+    /// original file was restored.
+    #[error("verification after write failed ({reason}), file restored: {path:?}")]
+    WriteVerification {
+        /// Path of the file
+        path: PathBuf,
+        /// What did not match
+        reason: &'static str,
+    },
+
+    /// The baseline file could not be parsed (or serialized)
+    #[error("invalid baseline file: {path:?}")]
+    InvalidBaseline {
+        /// Path of the baseline file
+        path: PathBuf,
+        /// Error from parsing the file
+        source: serde_json::Error,
+    },
+
+    /// The baseline file was written by a version of the tool with a
+    /// different format
+    #[error("unsupported baseline version {version} (expected {expected}): {path:?}")]
+    UnsupportedBaselineVersion {
+        /// Path of the baseline file
+        path: PathBuf,
+        /// Version found in the file
+        version: u32,
+        /// Version this build reads and writes
+        expected: u32,
+    },
+
+    /// The project config has a setting that is not valid
+    #[error("invalid project config ({message}) in: {path:?}")]
+    InvalidProjectConfig {
+        /// Path of the project config file
+        path: PathBuf,
+        /// What is not valid
+        message: String,
+    },
+
+    /// An include or exclude pattern for the files to process is not valid
+    #[error("invalid file pattern {pattern:?} for files in: {path:?}")]
+    InvalidPattern {
+        /// Path of the folder the pattern applies to
+        path: PathBuf,
+        /// The pattern
+        pattern: String,
+        /// Error from parsing the pattern
+        source: ignore::Error,
+    },
+
+    /// A symbolic link was found and the symlink policy is to report them
+    #[error("symbolic link not allowed by the symlink policy at: {path:?}")]
+    SymlinkNotAllowed {
+        /// Path of the link
+        path: PathBuf,
+    },
+
+    /// A symbolic link leads back to a folder that is already being walked
+    #[error("symbolic link cycle (folder is already being walked) at: {path:?}")]
+    SymlinkCycle {
+        /// Path of the link
+        path: PathBuf,
+    },
+
+    /// The git history of a file could not be read
+    #[error("failed to read git history ({message}) of: {path:?}")]
+    GitHistory {
+        /// Path of the file
+        path: PathBuf,
+        /// Why the history could not be read
+        message: String,
+    },
+
+    /// A file that would be created already exists
+    #[error("file already exists: {path:?}")]
+    FileExists {
+        /// Path of the file
+        path: PathBuf,
+    },
+
+    /// A suppression comment in the front matter is not valid
+    #[error(
+        "invalid suppression comment on line {line} of the front matter ({message}) in: {path:?}"
+    )]
+    InvalidSuppression {
+        /// Path of the file
+        path: PathBuf,
+        /// Line of the front matter the comment is on
+        line: usize,
+        /// What is not valid
+        message: String,
+    },
+}
+
+impl Error {
+    /// The path the error relates to
+    pub fn path(&self) -> &Path {
+        match self {
+            Error::VcsNotClean { path, .. }
+            | Error::ConfigUnreadable { path, .. }
+            | Error::FrontMatterNotFound { path }
+            | Error::InvalidToml { path, .. }
+            | Error::Io { path, .. }
+            | Error::SectionFolderName { path }
+            | Error::TransparentNotSet { path }
+            | Error::NotChanged { path }
+            | Error::StrictWriteRefused { path }
+            | Error::WriteVerification { path, .. }
+            | Error::InvalidBaseline { path, .. }
+            | Error::UnsupportedBaselineVersion { path, .. }
+            | Error::InvalidProjectConfig { path, .. }
+            | Error::InvalidPattern { path, .. }
+            | Error::SymlinkNotAllowed { path }
+            | Error::SymlinkCycle { path }
+            | Error::GitHistory { path, .. }
+            | Error::FileExists { path }
+            | Error::InvalidSuppression { path, .. } => path,
+        }
+    }
+
+    pub(crate) fn io(path: &Path, context: &'static str, source: io::Error) -> Self {
+        Error::Io {
+            path: path.to_path_buf(),
+            context,
+            source,
+        }
+    }
+
+    pub(crate) fn invalid_toml(path: &Path, source: toml_edit::TomlError) -> Self {
+        Error::InvalidToml {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
+
+/// Displays an error followed by each of its sources
+#[derive(Debug)]
+pub struct ErrorChain<'a>(pub &'a (dyn std::error::Error + 'static));
+
+impl std::fmt::Display for ErrorChain<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)?;
+        let mut source = self.0.source();
+        while let Some(e) = source {
+            write!(f, ": {e}")?;
+            source = e.source();
+        }
+        Ok(())
+    }
+}
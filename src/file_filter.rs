@@ -0,0 +1,247 @@
+//! Decides which files in the content folder are processed
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ignore::{WalkBuilder, overrides::OverrideBuilder};
+use tracing::{trace, warn};
+
+use crate::{
+    Options, SymlinkPolicy,
+    error::{Error, Result},
+};
+
+/// Name of the files with patterns (same syntax as `.gitignore`) of files the
+/// tool should not process
+pub const IGNORE_FILE: &str = ".abridgehelperignore";
+
+/// The files and folders under the content folder that should be processed
+///
+/// Excludes hidden files, files ignored by git or by an [`IGNORE_FILE`] and
+/// files that do not match the include and exclude patterns (as configured)
+#[derive(Debug, Default)]
+pub(crate) struct FileFilter {
+    included: HashSet<PathBuf>,
+}
+
+impl FileFilter {
+    /// Finds the files under the content folder of the site at `root_path`
+    /// that are included
+    pub(crate) fn new(root_path: &Path, options: &Options) -> Result<Self> {
+        let content_root = root_path.join("content");
+        let mut overrides = OverrideBuilder::new(&content_root);
+        // Section files are needed to know which section included pages are in
+        let section_files = (!options.include_globs.is_empty()).then(|| "**/_index.md".to_string());
+        let patterns = options
+            .include_globs
+            .iter()
+            .map(|glob| glob.to_string())
+            .chain(section_files)
+            .chain(options.exclude_globs.iter().map(|glob| format!("!{glob}")));
+        for pattern in patterns {
+            overrides
+                .add(&pattern)
+                .map_err(|source| Error::InvalidPattern {
+                    path: content_root.to_path_buf(),
+                    pattern: pattern.clone(),
+                    source,
+                })?;
+        }
+        let overrides = overrides.build().map_err(|source| Error::InvalidPattern {
+            path: content_root.to_path_buf(),
+            pattern: String::new(),
+            source,
+        })?;
+
+        // The walk starts at the root of the site (but only goes into the
+        // content folder) as patterns with a path in ignore files above the
+        // folder a walk starts in are not matched
+        let respect_gitignore = !options.no_gitignore;
+        let walk = WalkBuilder::new(root_path)
+            .hidden(!options.hidden)
+            .ignore(false)
+            .git_ignore(respect_gitignore)
+            .git_global(respect_gitignore)
+            .git_exclude(respect_gitignore)
+            .require_git(false)
+            // Links are only followed if the walk to process the files will
+            // follow them (Otherwise only the link itself is needed to skip or
+            // report it)
+            .follow_links(options.get_symlinks() == SymlinkPolicy::Follow)
+            .add_custom_ignore_filename(IGNORE_FILE)
+            .overrides(overrides)
+            .filter_entry(move |entry| entry.depth() != 1 || entry.path() == content_root)
+            .build();
+        let mut included = HashSet::new();
+        for entry in walk {
+            match entry {
+                Ok(entry) => {
+                    included.insert(entry.into_path());
+                }
+                // Links that lead back to a folder being walked are included so
+                // they can be reported when they are reached. Other errors are
+                // reported when the folder is walked to be processed.
+                Err(e) => match loop_child(&e) {
+                    Some(child) => {
+                        included.insert(child.to_path_buf());
+                    }
+                    None => trace!("Error while finding files to include: {e}"),
+                },
+            }
+        }
+        Ok(Self { included })
+    }
+
+    /// True if `path` should be processed
+    pub(crate) fn is_included(&self, path: &Path) -> bool {
+        self.included.contains(path)
+    }
+}
+
+/// The path of the link if `e` is a symbolic link that leads back to a folder
+/// being walked
+fn loop_child(e: &ignore::Error) -> Option<&Path> {
+    match e {
+        ignore::Error::Loop { child, .. } => Some(child),
+        ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => loop_child(err),
+        _ => None,
+    }
+}
+
+/// Canonical paths seen during the walk, used to avoid following symbolic link
+/// cycles and processing the same file more than once
+#[derive(Debug, Default)]
+pub(crate) struct Visited {
+    /// Folders currently being walked
+    ancestors: Vec<PathBuf>,
+    /// Files already processed
+    files: HashSet<PathBuf>,
+}
+
+impl Visited {
+    /// Returns true if `path` should be walked (or processed if it is a file)
+    /// taking into account the symlink `policy`
+    ///
+    /// Folders that are entered must be left using [`Visited::leave`] once
+    /// they have been walked
+    pub(crate) fn enter(&mut self, path: &Path, policy: SymlinkPolicy) -> Result<bool> {
+        let metadata = fs::symlink_metadata(path)
+            .map_err(|e| Error::io(path, "Failed to read metadata of file", e))?;
+        if metadata.is_symlink() {
+            match policy {
+                SymlinkPolicy::Follow => {}
+                SymlinkPolicy::Skip => {
+                    trace!("(Symlink)     {path:?}");
+                    return Ok(false);
+                }
+                SymlinkPolicy::Error => {
+                    return Err(Error::SymlinkNotAllowed {
+                        path: path.to_path_buf(),
+                    });
+                }
+            }
+        }
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| Error::io(path, "Failed to canonicalize path", e))?;
+        if canonical.is_dir() {
+            if self.ancestors.contains(&canonical) {
+                return Err(Error::SymlinkCycle {
+                    path: path.to_path_buf(),
+                });
+            }
+            self.ancestors.push(canonical);
+        } else if !self.files.insert(canonical) {
+            warn!("(Duplicate)   {path:?} is the same file as one already processed");
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Marks the folder entered last as walked
+    pub(crate) fn leave(&mut self) {
+        self.ancestors.pop();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::symlink;
+
+    use super::*;
+
+    #[test]
+    fn symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("a")).unwrap();
+        fs::write(root.join("a/page.md"), "").unwrap();
+        symlink(root.join("a"), root.join("b")).unwrap();
+        symlink(root, root.join("a/loop")).unwrap();
+
+        let mut visited = Visited::default();
+        assert!(visited.enter(root, SymlinkPolicy::Follow).unwrap());
+        assert!(
+            visited
+                .enter(&root.join("a"), SymlinkPolicy::Follow)
+                .unwrap()
+        );
+        assert!(
+            visited
+                .enter(&root.join("a/page.md"), SymlinkPolicy::Follow)
+                .unwrap()
+        );
+        assert!(matches!(
+            visited.enter(&root.join("a/loop"), SymlinkPolicy::Follow),
+            Err(Error::SymlinkCycle { .. })
+        ));
+        visited.leave();
+        assert!(
+            visited
+                .enter(&root.join("b"), SymlinkPolicy::Follow)
+                .unwrap()
+        );
+        assert!(
+            !visited
+                .enter(&root.join("b/page.md"), SymlinkPolicy::Follow)
+                .unwrap()
+        );
+        visited.leave();
+        assert!(!visited.enter(&root.join("b"), SymlinkPolicy::Skip).unwrap());
+        assert!(matches!(
+            visited.enter(&root.join("b"), SymlinkPolicy::Error),
+            Err(Error::SymlinkNotAllowed { .. })
+        ));
+    }
+
+    #[test]
+    fn links_are_only_followed_by_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("site");
+        let outside = dir.path().join("outside");
+        fs::create_dir_all(root.join("content")).unwrap();
+        fs::create_dir(&outside).unwrap();
+        fs::write(outside.join("page.md"), "").unwrap();
+        symlink(&outside, root.join("content/linked")).unwrap();
+
+        for (policy, is_followed) in [
+            (SymlinkPolicy::Follow, true),
+            (SymlinkPolicy::Skip, false),
+            (SymlinkPolicy::Error, false),
+        ] {
+            let options = Options::new(&root).symlinks(Some(policy));
+            let filter = FileFilter::new(&root, &options).unwrap();
+            assert!(filter.is_included(&root.join("content/linked")));
+            assert_eq!(
+                filter.is_included(&root.join("content/linked/page.md")),
+                is_followed,
+                "{policy:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,284 @@
+//! Finds when the body of a page last changed using the git history
+
+use std::{
+    ffi::OsStr,
+    io::Write as _,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use toml_edit::Datetime;
+
+use crate::{
+    error::{Error, Result},
+    processing::FileData,
+};
+
+/// A commit that touched a file
+#[derive(Debug)]
+struct FileCommit {
+    hash: String,
+    /// Author date (strict ISO 8601)
+    date: String,
+    /// Path of the file in the commit relative to the root of the repository
+    path: String,
+}
+
+/// Author date of the last commit that changed the body of the page at `path`
+///
+/// Commits that only changed the front matter are not counted. Returns None if
+/// the page is not in the history or the body has not changed since the page
+/// was added (only committed changes are considered).
+pub(crate) fn last_body_change(path: &Path) -> Result<Option<Datetime>> {
+    let commits = file_commits(path)?;
+    let bodies = bodies_at(path, &commits)?;
+    // Each commit is compared with the one before it (the next one in the list)
+    for (commit, pair) in commits.iter().zip(bodies.windows(2)) {
+        if pair[0] != pair[1] {
+            return commit
+                .date
+                .parse()
+                .map(Some)
+                .map_err(|_| git_failed(path, format!("unexpected date {:?}", commit.date)));
+        }
+    }
+    Ok(None)
+}
+
+/// Commits that touched the file at `path` (newest first, following renames)
+fn file_commits(path: &Path) -> Result<Vec<FileCommit>> {
+    let file_name = path.file_name().unwrap_or_default();
+    let output = git(
+        path,
+        &[
+            "log".as_ref(),
+            "--follow".as_ref(),
+            "--name-only".as_ref(),
+            "--format=%x00%H %aI".as_ref(),
+            "--".as_ref(),
+            file_name,
+        ],
+        String::new(),
+    )?;
+    parse_log(path, &String::from_utf8_lossy(&output))
+}
+
+/// Reads the commits from the output of `git log` (Records without a file
+/// line are skipped, as git lists no files for merges and some renames)
+fn parse_log(path: &Path, output: &str) -> Result<Vec<FileCommit>> {
+    let mut result = Vec::new();
+    for record in output.split('\0').filter(|x| !x.trim().is_empty()) {
+        let mut lines = record.lines().filter(|x| !x.is_empty());
+        let header = lines.next().unwrap_or_default();
+        let Some((hash, date)) = header.split_once(' ') else {
+            return Err(git_failed(path, format!("unexpected log entry {record:?}")));
+        };
+        let Some(file) = lines.next() else {
+            continue;
+        };
+        result.push(FileCommit {
+            hash: hash.to_string(),
+            date: date.to_string(),
+            path: file.to_string(),
+        });
+    }
+    Ok(result)
+}
+
+/// The body of the page as it was in each of the `commits` (None if the file
+/// is not in the commit, as in one that deleted it, or the front matter could
+/// not be found)
+///
+/// Every version is read by a single `git cat-file` so the number of processes
+/// run does not grow with the length of the history
+fn bodies_at(path: &Path, commits: &[FileCommit]) -> Result<Vec<Option<String>>> {
+    let specs = commits
+        .iter()
+        .map(|commit| format!("{}:{}\n", commit.hash, commit.path))
+        .collect();
+    let output = git(path, &["cat-file".as_ref(), "--batch".as_ref()], specs)?;
+    let mut rest = output.as_slice();
+    let mut result = Vec::with_capacity(commits.len());
+    for _ in commits {
+        // Each object is a `<hash> <type> <size>` line followed by the content
+        // and a line break (or `<spec> missing` if it was not found)
+        let header_end = rest.iter().position(|x| *x == b'\n').unwrap_or(rest.len());
+        let header = String::from_utf8_lossy(&rest[..header_end]).into_owned();
+        rest = rest.get(header_end + 1..).unwrap_or_default();
+        if header.ends_with(" missing") {
+            result.push(None);
+            continue;
+        }
+        let size = match header.split(' ').collect::<Vec<_>>()[..] {
+            [_, "blob", size] => size.parse::<usize>().ok(),
+            _ => None,
+        };
+        let Some(bytes) = size.and_then(|size| rest.get(..size)) else {
+            return Err(git_failed(path, format!("unexpected object {header:?}")));
+        };
+        let text = String::from_utf8_lossy(bytes);
+        result.push(
+            FileData::new_from_str(path, &text)
+                .ok()
+                .map(|data| normalize(data.content())),
+        );
+        rest = rest.get(bytes.len() + 1..).unwrap_or_default();
+    }
+    Ok(result)
+}
+
+/// Removes differences in the body that do not change the page (line endings
+/// and trailing whitespace)
+fn normalize(content: &str) -> String {
+    content
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Runs git in the folder of `path` with `input` on stdin and returns stdout
+fn git(path: &Path, args: &[&OsStr], input: String) -> Result<Vec<u8>> {
+    let dir = path.parent().unwrap_or(path);
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::io(path, "Failed to run git", e))?;
+    // Written from another thread so git is not blocked writing stdout while
+    // the input is still being written
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child
+        .wait_with_output()
+        .map_err(|e| Error::io(path, "Failed to run git", e))?;
+    writer
+        .join()
+        .expect("writing to git does not panic")
+        .map_err(|e| Error::io(path, "Failed to write to git", e))?;
+    if !output.status.success() {
+        return Err(git_failed(
+            path,
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(output.stdout)
+}
+
+fn git_failed(path: &Path, message: String) -> Error {
+    Error::GitHistory {
+        path: path.to_path_buf(),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn log_records_without_files() {
+        let output = "\0a 2024-02-01T10:00:00Z\n\0b 2024-01-01T10:00:00Z\n\npost.md\n";
+        let commits = parse_log(Path::new("post.md"), output).unwrap();
+        let hashes: Vec<_> = commits.iter().map(|x| x.hash.as_str()).collect();
+        assert_eq!(hashes, ["b"]);
+        assert!(parse_log(Path::new("post.md"), "\0a\npost.md\n").is_err());
+    }
+
+    #[test]
+    fn body_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let page = dir.path().join("post.md");
+        let git = |args: &[&str], date: &str| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(dir.path())
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .env("GIT_AUTHOR_DATE", date)
+                .env("GIT_COMMITTER_DATE", date)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        let commit = |page: &Path, text: &str, date: &str| {
+            fs::write(page, text).unwrap();
+            git(&["add", "-A"], date);
+            git(&["commit", "-qm", "edit"], date);
+        };
+        let status = Command::new("git")
+            .args(["init", "-q"])
+            .arg(dir.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        commit(
+            &page,
+            "+++\ntitle = \"A\"\n+++\nBody\n",
+            "2024-01-01T10:00:00Z",
+        );
+        assert_eq!(last_body_change(&page).unwrap(), None);
+        commit(
+            &page,
+            "+++\ntitle = \"A\"\n+++\nNew body\n",
+            "2024-02-01T10:00:00Z",
+        );
+        commit(
+            &page,
+            "+++\ntitle = \"B\"\n+++\nNew body  \r\n",
+            "2024-03-01T10:00:00Z",
+        );
+        assert_eq!(
+            last_body_change(&page).unwrap().unwrap().to_string(),
+            "2024-02-01T10:00:00+00:00"
+        );
+
+        // Renames are followed
+        let moved = dir.path().join("moved.md");
+        let text = fs::read_to_string(&page).unwrap();
+        fs::remove_file(&page).unwrap();
+        commit(&moved, &text, "2024-04-01T10:00:00Z");
+        assert_eq!(
+            last_body_change(&moved).unwrap().unwrap().to_string(),
+            "2024-02-01T10:00:00+00:00"
+        );
+
+        // A merge of changes to the body on both branches
+        let body = |first: &str, last: &str| {
+            format!("+++\ntitle = \"B\"\n+++\n{first}\n\nMiddle\n\n{last}\n")
+        };
+        commit(&moved, &body("First", "Last"), "2024-05-01T10:00:00Z");
+        git(&["checkout", "-qb", "side"], "2024-05-01T10:00:00Z");
+        commit(&moved, &body("Side", "Last"), "2024-06-01T10:00:00Z");
+        git(&["checkout", "-q", "-"], "2024-06-01T10:00:00Z");
+        commit(&moved, &body("First", "Main"), "2024-07-01T10:00:00Z");
+        git(
+            &["merge", "-q", "--no-ff", "-m", "merge", "side"],
+            "2024-08-01T10:00:00Z",
+        );
+        assert_eq!(
+            last_body_change(&moved).unwrap().unwrap().to_string(),
+            "2024-07-01T10:00:00+00:00"
+        );
+
+        // A commit that deleted the page has no body
+        let text = fs::read_to_string(&moved).unwrap();
+        fs::remove_file(&moved).unwrap();
+        git(&["add", "-A"], "2024-09-01T10:00:00Z");
+        git(&["commit", "-qm", "delete"], "2024-09-01T10:00:00Z");
+        commit(&moved, &text, "2024-10-01T10:00:00Z");
+        assert_eq!(
+            last_body_change(&moved).unwrap().unwrap().to_string(),
+            "2024-10-01T10:00:00+00:00"
+        );
+    }
+}
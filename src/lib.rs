@@ -5,68 +5,164 @@
 #![deny(unused_crate_dependencies)]
 #![doc = include_str!("../README.md")]
 
+mod baseline;
 mod cli;
+mod dates;
+mod error;
+mod file_filter;
+mod history;
+mod lsp;
+mod options;
 mod processing;
+mod project_config;
+mod report;
+mod rule;
+mod scaffold;
 mod section_info;
+mod site;
 mod stats;
+mod suppression;
 
-use crate::processing::check_path;
-use anyhow::Context;
-use processing::validate_zola_config;
-use std::{ops::RangeInclusive, path::PathBuf, time::Instant};
+use crate::{
+    baseline::apply_baseline,
+    file_filter::{FileFilter, Visited},
+    processing::check_path,
+    site::check_site,
+};
+use processing::{filter_page, validate_zola_config};
+use std::{
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    time::Instant,
+};
 use tracing::info;
 use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt as _, util::SubscriberInitExt as _};
 use version_control_clean_check::{CheckOptions, check_version_control};
 
-pub use cli::Cli;
-pub use stats::Stats;
+pub use baseline::BaselineEntry;
+pub use cli::{
+    CheckArgs, Cli, Command, DraftPolicy, ExplainArgs, FixArgs, InitArgs, LspArgs, NewArgs,
+    OutputArgs, ReportArgs, RuleArgs, SiteArgs, SymlinkPolicy, WriteArgs,
+};
+pub use error::{Error, ErrorChain, Result};
+pub use file_filter::IGNORE_FILE;
+pub use lsp::run_lsp;
+pub use options::Options;
+pub use processing::FileData;
+pub use project_config::PROJECT_CONFIG_FILE;
+pub use report::{Diagnostic, Report};
+pub use rule::{Rule, Severity};
+pub use scaffold::{init_project_config, new_page};
+pub use stats::{RuleStats, SectionStats, Stats};
+pub use suppression::Suppression;
 
 const PREFERRED_RANGE: RangeInclusive<usize> = 140..=180;
 const TOML_KEY_EXTRA: &str = "extra";
 
 /// Runs the body of the logic
-pub fn run(cli: &Cli) -> anyhow::Result<Stats> {
+///
+/// Nothing is printed, everything found is returned in the report (Details
+/// are also logged using `tracing`)
+pub fn run(options: &Options) -> Result<Report> {
     // This also checks that the path exists as that is required for
     // canonicalization
-    let root_path = PathBuf::from(&cli.root_path)
-        .canonicalize()
-        .with_context(|| format!("Failed to canonicalize path: '{}'", cli.root_path))?;
+    let root_path = canonicalize_root(options)?;
 
     let check_options = CheckOptions {
         // This makes it possible for the user to undo our changes if any so this is fine
         allow_staged: true,
         // Set when dirty is allowed (Either we aren't going to make changes so it's fine or the
         // user opted into allowing dirty files)
-        allow_dirty: cli.should_check_only || cli.allow_dirty,
+        allow_dirty: options.check_only || options.allow_dirty,
         ..Default::default()
     };
 
     // Confirm it is safe to make changes
-    check_version_control(&root_path, &check_options).with_context(|| {
-        format!(
-            "Failed to find a clean version control system. Files must be at least staged before tool can run or you can opt-out of being able to revert changes. See help for more info.\nPath:{root_path:?}"
-        )
+    check_version_control(&root_path, &check_options).map_err(|source| Error::VcsNotClean {
+        path: root_path.clone(),
+        source,
     })?;
 
     let start = Instant::now();
 
+    let options = &options.clone().with_project_config(&root_path)?;
+
     //Check description in config file for SEO length
-    let mut result = validate_zola_config(&root_path.join("config.toml"), cli)?;
+    let mut result = validate_zola_config(&root_path.join("config.toml"), options)?;
 
     // Walk the content folder tree and process files
-    result += check_path(&root_path.join("content"), cli, None)?;
+    let content_root = root_path.join("content");
+    let filter = FileFilter::new(&root_path, options)?;
+    result += check_path(
+        &content_root,
+        options,
+        &filter,
+        &mut Visited::default(),
+        None,
+    );
+
+    // Checks that compare files with each other
+    let site_report = check_site(&root_path, result.site_files(), options);
+    result += site_report;
+
+    if let Some(baseline_path) = options.baseline.as_ref() {
+        apply_baseline(
+            baseline_path,
+            options.update_baseline,
+            &root_path,
+            &mut result,
+        )?;
+    }
     info!(
         "Run duration: {} ms",
         Instant::now().duration_since(start).as_millis()
     );
-    println!("Run Completed");
     Ok(result)
 }
 
+/// Runs the checks and fixes on a single page passed in as `input` instead of
+/// walking the content folder
+///
+/// `page_path` is where the page would be on disk and is used to determine
+/// which section the page belongs to. Nothing is written to disk, instead the
+/// resulting text of the page is returned (unchanged if no fixes were needed).
+pub fn run_filter(options: &Options, page_path: &Path, input: &str) -> Result<(String, Report)> {
+    let root_path = canonicalize_root(options)?;
+    let options = &options.clone().with_project_config(&root_path)?;
+    let page_path = resolve_virtual_path(page_path)
+        .map_err(|e| Error::io(page_path, "Failed to resolve path", e))?;
+    filter_page(&root_path.join("content"), &page_path, input, options)
+}
+
+fn canonicalize_root(options: &Options) -> Result<PathBuf> {
+    options
+        .root_path
+        .canonicalize()
+        .map_err(|e| Error::io(&options.root_path, "Failed to canonicalize path", e))
+}
+
+/// Makes `path` absolute without requiring the file to exist
+///
+/// The parent folder is canonicalized if it exists so that the result can be
+/// compared with the (canonicalized) root path
+fn resolve_virtual_path(path: &Path) -> std::io::Result<PathBuf> {
+    let path = std::path::absolute(path)?;
+    let (Some(parent), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return Ok(path);
+    };
+    Ok(match parent.canonicalize() {
+        Ok(parent) => parent.join(file_name),
+        Err(_) => path,
+    })
+}
+
 /// Initializes tracing
+///
+/// Output is written to stderr so that stdout only contains the results of the
+/// run (required for use as a filter)
 pub fn init_tracing() {
     tracing_subscriber::registry()
-        .with(fmt::layer())
+        .with(fmt::layer().with_writer(std::io::stderr))
         .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
         .init();
 }
@@ -0,0 +1,843 @@
+//! Language server that publishes the results of the checks for open files and
+//! offers the fixes as code actions
+
+use std::{
+    collections::HashMap,
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::Context;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams,
+    CodeActionProviderCapability, Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DidSaveTextDocumentParams,
+    NumberOrString, Position, PublishDiagnosticsParams, ServerCapabilities,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions, TextEdit, Url,
+    WorkspaceEdit,
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+        Notification as _, PublishDiagnostics,
+    },
+    request::{CodeActionRequest, Request as _},
+};
+use toml_edit::{DocumentMut, ImDocument, TableLike};
+use tracing::{debug, info, warn};
+
+use crate::{
+    Error, ErrorChain, FileData, Options, PROJECT_CONFIG_FILE, TOML_KEY_EXTRA,
+    dates::{self, find_date_issues},
+    processing::{
+        DescriptionIssue, find_description_issue, find_section_info, front_matter_span,
+        is_transparent_set, page_settings, set_series, set_tags,
+    },
+    rule::{Rule, Severity},
+    section_info::find_setting_issues,
+    suppression::parse_suppressions,
+};
+
+const SOURCE: &str = "zola_abridge_helper";
+
+/// Runs the language server over stdio until the client requests a shutdown
+pub fn run_lsp(options: &Options) -> anyhow::Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Options(
+            TextDocumentSyncOptions {
+                open_close: Some(true),
+                change: Some(TextDocumentSyncKind::FULL),
+                save: Some(true.into()),
+                ..Default::default()
+            },
+        )),
+        code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+        ..Default::default()
+    };
+    connection
+        .initialize(serde_json::to_value(capabilities)?)
+        .context("Failed to initialize language server")?;
+    info!("Language server initialized");
+    Server::new(options, &connection).main_loop()?;
+    drop(connection);
+    io_threads
+        .join()
+        .context("Failed to join language server IO threads")?;
+    info!("Language server stopped");
+    Ok(())
+}
+
+/// A problem found in a document and the fix for it if one is available
+struct Finding {
+    diagnostic: Diagnostic,
+    fix: Option<CodeAction>,
+}
+
+/// Options of a site with its project config applied
+struct SiteConfig {
+    /// When the project config was last modified (None if there is none)
+    modified: Option<SystemTime>,
+    /// Error message if the project config failed to load
+    options: Result<Options, String>,
+}
+
+struct Server<'a> {
+    options: &'a Options,
+    connection: &'a Connection,
+    /// Text of the open documents
+    documents: HashMap<Url, String>,
+    /// Project configs by the root folder of the site
+    configs: HashMap<PathBuf, SiteConfig>,
+}
+
+impl<'a> Server<'a> {
+    fn new(options: &'a Options, connection: &'a Connection) -> Self {
+        Self {
+            options,
+            connection,
+            documents: HashMap::new(),
+            configs: HashMap::new(),
+        }
+    }
+
+    fn main_loop(&mut self) -> anyhow::Result<()> {
+        let connection = self.connection;
+        for msg in &connection.receiver {
+            // A bad message should not stop the server so errors are only logged
+            let result = match msg {
+                Message::Request(req) => {
+                    if connection.handle_shutdown(&req)? {
+                        return Ok(());
+                    }
+                    self.handle_request(req)
+                }
+                Message::Notification(not) => self.handle_notification(not),
+                Message::Response(_) => Ok(()),
+            };
+            if let Err(e) = result {
+                warn!("{e:#}");
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&mut self, req: Request) -> anyhow::Result<()> {
+        let response = match req.method.as_str() {
+            CodeActionRequest::METHOD => {
+                let (id, params) = req.extract::<CodeActionParams>(CodeActionRequest::METHOD)?;
+                let actions = self.code_actions(&params);
+                Response::new_ok(id, actions)
+            }
+            _ => Response::new_err(
+                req.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("Unsupported request: {}", req.method),
+            ),
+        };
+        self.connection.sender.send(response.into())?;
+        Ok(())
+    }
+
+    fn handle_notification(&mut self, not: Notification) -> anyhow::Result<()> {
+        match not.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams = not.extract(DidOpenTextDocument::METHOD)?;
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), params.text_document.text);
+                self.publish(&uri)?;
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams =
+                    not.extract(DidChangeTextDocument::METHOD)?;
+                // Only full document sync is supported so the last change is the whole text
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    let uri = params.text_document.uri;
+                    self.documents.insert(uri.clone(), change.text);
+                    self.publish(&uri)?;
+                }
+            }
+            DidSaveTextDocument::METHOD => {
+                let params: DidSaveTextDocumentParams = not.extract(DidSaveTextDocument::METHOD)?;
+                let path = params.text_document.uri.path();
+                if path.ends_with("_index.md") || path.ends_with(PROJECT_CONFIG_FILE) {
+                    // Settings are read from disk so pages need to be checked again
+                    let uris: Vec<Url> = self.documents.keys().cloned().collect();
+                    for uri in uris {
+                        self.publish(&uri)?;
+                    }
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams =
+                    not.extract(DidCloseTextDocument::METHOD)?;
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri);
+                self.send_diagnostics(uri, Vec::new())?;
+            }
+            _ => debug!("Ignored notification: {}", not.method),
+        }
+        Ok(())
+    }
+
+    fn publish(&mut self, uri: &Url) -> anyhow::Result<()> {
+        let diagnostics = self
+            .findings(uri)
+            .into_iter()
+            .map(|finding| finding.diagnostic)
+            .collect();
+        self.send_diagnostics(uri.clone(), diagnostics)
+    }
+
+    /// Runs the checks on an open document with the project config of the site
+    /// it is part of
+    fn findings(&mut self, uri: &Url) -> Vec<Finding> {
+        let site_root = uri
+            .to_file_path()
+            .ok()
+            .and_then(|path| find_site_root(&path));
+        let options = match site_root {
+            Some(root_path) => self.site_options(root_path),
+            None => Ok(self.options.clone()),
+        };
+        let Some(text) = self.documents.get(uri) else {
+            return Vec::new();
+        };
+        match options {
+            Ok(options) => analyze(&options, uri, text),
+            Err(message) => {
+                let mut result = analyze(self.options, uri, text);
+                result.push(Finding {
+                    diagnostic: new_diagnostic(
+                        lsp_types::Range::default(),
+                        DiagnosticSeverity::ERROR,
+                        message,
+                    ),
+                    fix: None,
+                });
+                result
+            }
+        }
+    }
+
+    /// Options for the site at `root_path` (The project config is only read
+    /// again when it has been modified)
+    fn site_options(&mut self, root_path: PathBuf) -> Result<Options, String> {
+        let modified = fs::metadata(root_path.join(PROJECT_CONFIG_FILE))
+            .and_then(|x| x.modified())
+            .ok();
+        if let Some(config) = self.configs.get(&root_path)
+            && config.modified == modified
+        {
+            return config.options.clone();
+        }
+        let options = self
+            .options
+            .clone()
+            .with_project_config(&root_path)
+            .map_err(|e| {
+                warn!("{}", ErrorChain(&e));
+                ErrorChain(&e).to_string()
+            });
+        self.configs.insert(
+            root_path,
+            SiteConfig {
+                modified,
+                options: options.clone(),
+            },
+        );
+        options
+    }
+
+    fn send_diagnostics(&self, uri: Url, diagnostics: Vec<Diagnostic>) -> anyhow::Result<()> {
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+        let not = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+        self.connection.sender.send(not.into())?;
+        Ok(())
+    }
+
+    fn code_actions(&mut self, params: &CodeActionParams) -> Vec<CodeActionOrCommand> {
+        let mut result: Vec<CodeActionOrCommand> = Vec::new();
+        for finding in self.findings(&params.text_document.uri) {
+            let Some(mut fix) = finding.fix else {
+                continue;
+            };
+            if !ranges_overlap(&finding.diagnostic.range, &params.range) {
+                continue;
+            }
+            // The same fix can be offered by more than one diagnostic
+            if result
+                .iter()
+                .any(|x| matches!(x, CodeActionOrCommand::CodeAction(x) if x.title == fix.title))
+            {
+                continue;
+            }
+            fix.diagnostics = Some(vec![finding.diagnostic]);
+            result.push(fix.into());
+        }
+        result
+    }
+}
+
+/// Runs the checks on the text of a document (`options` must already have the
+/// project config of the site applied)
+fn analyze(options: &Options, uri: &Url, text: &str) -> Vec<Finding> {
+    let Ok(path) = uri.to_file_path() else {
+        return Vec::new();
+    };
+    if path.extension().is_none_or(|ext| ext != "md") {
+        return Vec::new();
+    }
+    let mut result = Vec::new();
+    let Some(fm_span) = front_matter_span(text) else {
+        result.push(Finding {
+            diagnostic: new_diagnostic(
+                lsp_types::Range::default(),
+                DiagnosticSeverity::ERROR,
+                "Failed to find front matter".to_string(),
+            ),
+            fix: None,
+        });
+        return result;
+    };
+    let fm = &text[fm_span.clone()];
+    let doc = match ImDocument::parse(fm) {
+        Ok(doc) => doc,
+        Err(e) => {
+            let span = e.span().unwrap_or(0..0);
+            result.push(Finding {
+                diagnostic: new_diagnostic(
+                    to_lsp_range(text, fm_span.start + span.start..fm_span.start + span.end),
+                    DiagnosticSeverity::ERROR,
+                    format!("Failed to parse TOML in front matter: {}", e.message()),
+                ),
+                fix: None,
+            });
+            return result;
+        }
+    };
+    let locator = Locator {
+        uri,
+        text,
+        fm_span: &fm_span,
+    };
+    let content_root = find_site_root(&path).map(|dir| dir.join("content"));
+
+    let (suppressions, invalid) = parse_suppressions(&path, fm);
+    for e in invalid {
+        let line = match &e {
+            Error::InvalidSuppression { line, .. } => *line,
+            _ => 0,
+        };
+        let start = to_position(text, fm_span.start);
+        let range = lsp_types::Range::new(
+            Position::new(start.line + line as u32, 0),
+            Position::new(start.line + line as u32 + 1, 0),
+        );
+        result.push(Finding {
+            diagnostic: new_diagnostic(range, DiagnosticSeverity::ERROR, e.to_string()),
+            fix: None,
+        });
+    }
+    let is_suppressed = |rule| suppressions.iter().any(|x| x.rule() == rule);
+
+    let severity = options.rule_severity(Rule::InvalidSetting);
+    if severity != Severity::Off && !is_suppressed(Rule::InvalidSetting) {
+        for issue in find_setting_issues(doc.as_table()) {
+            result.push(Finding {
+                diagnostic: rule_diagnostic(
+                    locator.key_range(doc.as_table(), &[TOML_KEY_EXTRA, issue.key()]),
+                    Rule::InvalidSetting,
+                    severity,
+                    issue.to_string(),
+                ),
+                fix: None,
+            });
+        }
+    }
+
+    if path.ends_with("_index.md") {
+        let severity = options.rule_severity(Rule::TransparentNotSet);
+        if !is_transparent_set(doc.as_table())
+            && severity != Severity::Off
+            && !is_suppressed(Rule::TransparentNotSet)
+        {
+            let is_missing = doc.get("transparent").is_none();
+            result.push(Finding {
+                diagnostic: rule_diagnostic(
+                    locator.key_range(doc.as_table(), &["transparent"]),
+                    Rule::TransparentNotSet,
+                    severity,
+                    "Transparent not set or not bool for section".to_string(),
+                ),
+                fix: is_missing.then(|| locator.insert_key("transparent = false")),
+            });
+        }
+        return result;
+    }
+
+    let options = &*options.for_page(doc.as_table());
+    let section_info = match content_root.map(|x| find_section_info(&x, &path, options)) {
+        Some(Ok(section_info)) => section_info,
+        Some(Err(e)) => {
+            // Usually the front matter of the section failed to parse
+            warn!("{}", ErrorChain(&e));
+            result.push(Finding {
+                diagnostic: new_diagnostic(
+                    locator.opening_line_range(),
+                    DiagnosticSeverity::ERROR,
+                    ErrorChain(&e).to_string(),
+                ),
+                fix: None,
+            });
+            None
+        }
+        None => None,
+    };
+    let section_info = section_info
+        .as_ref()
+        .map(|info| page_settings(info, doc.as_table(), &suppressions, options));
+
+    if !section_info
+        .as_ref()
+        .is_some_and(|info| info.disable_check_description)
+    {
+        match find_description_issue(doc.as_table()) {
+            None => {}
+            Some(issue)
+                if options.rule_severity(issue.rule()) == Severity::Off
+                    || is_suppressed(issue.rule())
+                    || section_info
+                        .as_ref()
+                        .is_some_and(|info| info.is_suppressed(issue.rule())) => {}
+            Some(issue) => result.push(Finding {
+                diagnostic: rule_diagnostic(
+                    locator.key_range(doc.as_table(), &["description"]),
+                    issue.rule(),
+                    options.rule_severity(issue.rule()),
+                    issue.to_string(),
+                ),
+                fix: (issue == DescriptionIssue::Missing)
+                    .then(|| locator.insert_key("description = \"\"")),
+            }),
+        }
+    }
+
+    for issue in find_date_issues(doc.as_table(), dates::now()) {
+        let rule = issue.rule();
+        let severity = options.rule_severity(rule);
+        if severity == Severity::Off
+            || is_suppressed(rule)
+            || section_info
+                .as_ref()
+                .is_some_and(|info| info.is_suppressed(rule))
+        {
+            continue;
+        }
+        result.push(Finding {
+            diagnostic: rule_diagnostic(
+                locator.key_range(doc.as_table(), &[issue.key()]),
+                rule,
+                severity,
+                issue.to_string(),
+            ),
+            fix: None,
+        });
+    }
+
+    let sort_key_severity = options.rule_severity(Rule::SortKeyMissing);
+    if let Some(section_info) = section_info.as_deref()
+        && let Some(issue) = section_info
+            .sort_by
+            .and_then(|sort_by| sort_by.find_issue(doc.as_table()))
+        && sort_key_severity != Severity::Off
+        && !section_info.is_suppressed(Rule::SortKeyMissing)
+    {
+        result.push(Finding {
+            diagnostic: rule_diagnostic(
+                locator.key_range(doc.as_table(), &[issue.key()]),
+                Rule::SortKeyMissing,
+                sort_key_severity,
+                issue.to_string(),
+            ),
+            fix: None,
+        });
+    }
+
+    if let Some(section_info) = section_info.as_deref() {
+        let Ok(mut doc_mut) = fm.parse::<DocumentMut>() else {
+            return result;
+        };
+        let is_series_changed = set_series(&mut doc_mut, section_info);
+        let is_tags_changed = set_tags(&mut doc_mut, section_info);
+        // Set the same way as for a write so the line endings are kept
+        let Ok(mut data) = FileData::new_from_str(&path, text) else {
+            return result;
+        };
+        data.set_front_matter(&doc_mut);
+        let fix = CodeAction {
+            title: "Set series and tags to section name".to_string(),
+            kind: Some(CodeActionKind::QUICKFIX),
+            edit: Some(locator.edit(fm_span.clone(), data.front_matter().to_string())),
+            is_preferred: Some(true),
+            ..Default::default()
+        };
+        let section_name = section_info.section_name();
+        let is_reported = |rule| {
+            options.rule_severity(rule) != Severity::Off
+                && !is_suppressed(rule)
+                && !section_info.is_suppressed(rule)
+        };
+        if is_series_changed && is_reported(Rule::SeriesNotSet) {
+            result.push(Finding {
+                diagnostic: rule_diagnostic(
+                    locator.key_range(doc.as_table(), &["extra", "series"]),
+                    Rule::SeriesNotSet,
+                    options.rule_severity(Rule::SeriesNotSet),
+                    format!("Series should be set to the section name: {section_name:?}"),
+                ),
+                fix: Some(fix.clone()),
+            });
+        }
+        if is_tags_changed && is_reported(Rule::TagsMissingSection) {
+            result.push(Finding {
+                diagnostic: rule_diagnostic(
+                    locator.key_range(doc.as_table(), &["taxonomies", "tags"]),
+                    Rule::TagsMissingSection,
+                    options.rule_severity(Rule::TagsMissingSection),
+                    format!("Tags should include the section name: {section_name:?}"),
+                ),
+                fix: Some(fix),
+            });
+        }
+    }
+
+    result
+}
+
+/// Maps locations in the front matter back to the document
+struct Locator<'a> {
+    uri: &'a Url,
+    text: &'a str,
+    fm_span: &'a Range<usize>,
+}
+
+impl Locator<'_> {
+    /// Range of the key found by following `keys` from `table` or the line
+    /// that starts the front matter if the key is not found
+    fn key_range(&self, table: &dyn TableLike, keys: &[&str]) -> lsp_types::Range {
+        let mut table = Some(table);
+        let mut span = None;
+        for key in keys {
+            let Some((k, item)) = table.and_then(|t| t.get_key_value(key)) else {
+                break;
+            };
+            span = k.span().or(span);
+            table = item.as_table_like();
+        }
+        match span {
+            Some(span) => to_lsp_range(
+                self.text,
+                self.fm_span.start + span.start..self.fm_span.start + span.end,
+            ),
+            None => self.opening_line_range(),
+        }
+    }
+
+    /// Range of the `+++` that starts the front matter
+    fn opening_line_range(&self) -> lsp_types::Range {
+        let end = to_position(self.text, self.fm_span.start);
+        lsp_types::Range::new(Position::new(end.line, 0), end)
+    }
+
+    /// Fix that inserts `line` at the start of the front matter so that it is
+    /// not part of any table
+    fn insert_key(&self, line: &str) -> CodeAction {
+        let line_break = if self.text[self.fm_span.start..].starts_with("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let offset = self.fm_span.start + line_break.len();
+        CodeAction {
+            title: format!("Insert `{line}`"),
+            kind: Some(CodeActionKind::QUICKFIX),
+            edit: Some(self.edit(offset..offset, format!("{line}{line_break}"))),
+            ..Default::default()
+        }
+    }
+
+    fn edit(&self, span: Range<usize>, new_text: String) -> WorkspaceEdit {
+        let edit = TextEdit::new(to_lsp_range(self.text, span), new_text);
+        WorkspaceEdit::new(HashMap::from([(self.uri.clone(), vec![edit])]))
+    }
+}
+
+fn new_diagnostic(
+    range: lsp_types::Range,
+    severity: DiagnosticSeverity,
+    message: String,
+) -> Diagnostic {
+    Diagnostic {
+        range,
+        severity: Some(severity),
+        source: Some(SOURCE.to_string()),
+        message,
+        ..Default::default()
+    }
+}
+
+/// Creates a diagnostic for a problem found by `rule` (`severity` must not be
+/// [`Severity::Off`])
+fn rule_diagnostic(
+    range: lsp_types::Range,
+    rule: Rule,
+    severity: Severity,
+    message: String,
+) -> Diagnostic {
+    let severity = match severity {
+        Severity::Error => DiagnosticSeverity::ERROR,
+        Severity::Warning => DiagnosticSeverity::WARNING,
+        Severity::Info | Severity::Off => DiagnosticSeverity::INFORMATION,
+    };
+    Diagnostic {
+        code: Some(NumberOrString::String(rule.id().to_string())),
+        ..new_diagnostic(range, severity, message)
+    }
+}
+
+/// Finds the root folder of the zola site the file at `path` is part of
+fn find_site_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .skip(1)
+        .find(|dir| dir.join("config.toml").is_file())
+        .map(Path::to_path_buf)
+}
+
+fn ranges_overlap(a: &lsp_types::Range, b: &lsp_types::Range) -> bool {
+    a.start.line <= b.end.line && b.start.line <= a.end.line
+}
+
+fn to_lsp_range(text: &str, span: Range<usize>) -> lsp_types::Range {
+    lsp_types::Range::new(to_position(text, span.start), to_position(text, span.end))
+}
+
+/// Converts a byte offset into a position (Columns are in UTF-16 code units as
+/// required by the protocol)
+fn to_position(text: &str, offset: usize) -> Position {
+    let before = &text[..offset.min(text.len())];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line = before.matches('\n').count();
+    let character = before[line_start..].encode_utf16().count();
+    Position::new(line as u32, character as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_server::RequestId;
+    use lsp_types::{
+        TextDocumentIdentifier, TextDocumentItem, VersionedTextDocumentIdentifier,
+        request::Shutdown,
+    };
+    use tempfile::TempDir;
+
+    use super::*;
+
+    const PAGE: &str = "+++\ntitle = \"Post\"\n+++\nBody\n";
+
+    /// Site with a `rust` section (The page `rust/post.md` is not written)
+    fn site() -> (TempDir, Url) {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("config.toml"), "").unwrap();
+        let section = dir.path().join("content/rust");
+        fs::create_dir_all(&section).unwrap();
+        fs::write(
+            section.join("_index.md"),
+            "+++\ntitle = \"Rust\"\ntransparent = false\n+++\n",
+        )
+        .unwrap();
+        let uri = Url::from_file_path(section.join("post.md")).unwrap();
+        (dir, uri)
+    }
+
+    fn open_notification(uri: &Url, text: &str) -> Notification {
+        let params = DidOpenTextDocumentParams {
+            text_document: TextDocumentItem::new(uri.clone(), "markdown".into(), 1, text.into()),
+        };
+        Notification::new(DidOpenTextDocument::METHOD.to_string(), params)
+    }
+
+    /// Codes of the diagnostics published for `uri`
+    fn published_codes(client: &Connection, uri: &Url) -> Vec<String> {
+        let Message::Notification(not) = client.receiver.recv().unwrap() else {
+            panic!("expected a notification");
+        };
+        let params: PublishDiagnosticsParams = not.extract(PublishDiagnostics::METHOD).unwrap();
+        assert_eq!(&params.uri, uri);
+        params
+            .diagnostics
+            .into_iter()
+            .map(|x| match x.code {
+                Some(NumberOrString::String(code)) => code,
+                _ => x.message,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn diagnostics_on_open_and_change() {
+        let (dir, uri) = site();
+        let options = Options::new(dir.path());
+        let (connection, client) = Connection::memory();
+        let mut server = Server::new(&options, &connection);
+
+        server
+            .handle_notification(open_notification(&uri, PAGE))
+            .unwrap();
+        assert_eq!(
+            published_codes(&client, &uri),
+            [
+                "description-missing",
+                "series-not-set",
+                "tags-missing-section"
+            ]
+        );
+
+        let params = DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri.clone(), 2),
+            content_changes: vec![lsp_types::TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: "+++\ntitle = \"Post\"\ndescription = \"About\"\ndate = \"Tomorrow\"\n\n[extra]\nseries = \"Rust\"\n\n[taxonomies]\ntags = [\"Rust\"]\n+++\nBody\n".into(),
+            }],
+        };
+        server
+            .handle_notification(Notification::new(
+                DidChangeTextDocument::METHOD.to_string(),
+                params,
+            ))
+            .unwrap();
+        assert_eq!(
+            published_codes(&client, &uri),
+            ["description-length", "invalid-date"]
+        );
+    }
+
+    /// Titles and new text of the code actions for `text` opened as `uri`
+    fn code_action_edits(dir: &TempDir, uri: &Url, text: &str) -> Vec<(String, String)> {
+        let options = Options::new(dir.path());
+        let (connection, client) = Connection::memory();
+        let mut server = Server::new(&options, &connection);
+        server
+            .handle_notification(open_notification(uri, text))
+            .unwrap();
+        let _ = published_codes(&client, uri);
+
+        let params = CodeActionParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            range: lsp_types::Range::new(Position::new(0, 0), Position::new(4, 0)),
+            context: Default::default(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        server
+            .code_actions(&params)
+            .into_iter()
+            .map(|action| {
+                let CodeActionOrCommand::CodeAction(action) = action else {
+                    panic!("expected a code action");
+                };
+                let mut changes = action.edit.unwrap().changes.unwrap();
+                let edit = changes.remove(uri).unwrap().remove(0);
+                (action.title, edit.new_text)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn code_actions_fix_findings() {
+        let (dir, uri) = site();
+        assert_eq!(
+            code_action_edits(&dir, &uri, PAGE),
+            [
+                (
+                    "Insert `description = \"\"`".to_string(),
+                    "description = \"\"\n".to_string()
+                ),
+                (
+                    "Set series and tags to section name".to_string(),
+                    "\ntitle = \"Post\"\nextra = { series = \"Rust\" }\ntaxonomies = { tags = [\"Rust\"] }\n".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn code_actions_keep_crlf() {
+        let (dir, uri) = site();
+        // The BOM is outside of the front matter that is replaced
+        let text = "\u{feff}+++\r\ntitle = \"Post\"\r\n+++\r\nBody\r\n";
+        assert_eq!(
+            code_action_edits(&dir, &uri, text),
+            [
+                (
+                    "Insert `description = \"\"`".to_string(),
+                    "description = \"\"\r\n".to_string()
+                ),
+                (
+                    "Set series and tags to section name".to_string(),
+                    "\r\ntitle = \"Post\"\r\nextra = { series = \"Rust\" }\r\ntaxonomies = { tags = [\"Rust\"] }\r\n".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn off_and_suppressed_rules_are_skipped() {
+        let (dir, uri) = site();
+        fs::write(
+            dir.path().join(PROJECT_CONFIG_FILE),
+            "[rules]\nseries-not-set = \"off\"\n",
+        )
+        .unwrap();
+        let options = Options::new(dir.path());
+        let (connection, client) = Connection::memory();
+        let mut server = Server::new(&options, &connection);
+        let text = "+++\n# abridge-helper: ignore tags-missing-section reason=\"about\"\n# abridge-helper: ignore description-missing reason=\"draft\"\ntitle = \"Post\"\n+++\nBody\n";
+        server
+            .handle_notification(open_notification(&uri, text))
+            .unwrap();
+        assert_eq!(published_codes(&client, &uri), Vec::<String>::new());
+    }
+
+    #[test]
+    fn errors_do_not_stop_the_server() {
+        let (dir, uri) = site();
+        let options = Options::new(dir.path());
+        let (connection, client) = Connection::memory();
+        std::thread::scope(|scope| {
+            let server = scope.spawn(|| Server::new(&options, &connection).main_loop());
+            // Dropped on a failed assert so the server stops instead of hanging
+            let client = client;
+            let bad = Notification::new(DidOpenTextDocument::METHOD.to_string(), "not params");
+            client.sender.send(bad.into()).unwrap();
+            client
+                .sender
+                .send(open_notification(&uri, "+++\ntitle = \n+++\n").into())
+                .unwrap();
+            assert_eq!(
+                published_codes(&client, &uri),
+                ["Failed to parse TOML in front matter: invalid string\nexpected `\"`, `'`"]
+            );
+
+            let shutdown = Request::new(RequestId::from(1), Shutdown::METHOD.to_string(), ());
+            client.sender.send(shutdown.into()).unwrap();
+            let _ = client.receiver.recv().unwrap();
+            let exit = Notification::new("exit".to_string(), ());
+            client.sender.send(exit.into()).unwrap();
+            server.join().unwrap().unwrap();
+        });
+    }
+}
@@ -1,36 +1,233 @@
-use anyhow::bail;
+use std::{
+    collections::BTreeMap,
+    io::{Read as _, Write as _},
+    path::Path,
+};
+
+use anyhow::{Context as _, bail};
 use clap::Parser;
 use tracing::{debug, error};
 
-use zola_abridge_helper::{self, Cli, init_tracing, run};
+use zola_abridge_helper::{
+    self, Cli, Command, Diagnostic, ErrorChain, Options, OutputArgs, PROJECT_CONFIG_FILE, Report,
+    Rule, Severity, init_project_config, init_tracing, new_page, run, run_filter, run_lsp,
+};
 
 fn main() -> anyhow::Result<()> {
     let cli: Cli = Cli::parse();
     init_tracing();
     debug!("Cli: {cli:#?}");
-    let stats = run(&cli)?;
-    println!("File Stats: {stats}");
-    if stats.errors() == 0 {
-        if cli.should_check_only && stats.changed() > 0 {
+    match &cli.command {
+        None => {
+            let options = Options::from(&cli);
+            if let Some(page_path) = cli.stdin_filepath.as_ref() {
+                return filter(&options, Path::new(page_path));
+            }
+            finish(&run(&options)?, &cli.output)
+        }
+        Some(Command::Check(args)) => finish(&run(&Options::from(args))?, &args.output),
+        Some(Command::Fix(args)) => finish(&run(&Options::from(args))?, &args.output),
+        Some(Command::Report(args)) => print_report(&run(&Options::from(args))?, args.json),
+        Some(Command::Init(args)) => {
+            let path = init_project_config(Path::new(&args.root_path), args.force)?;
+            println!("Created {path:?}");
+            Ok(())
+        }
+        Some(Command::Explain(args)) => explain(args.rule.as_deref()),
+        Some(Command::New(args)) => {
+            new_page(
+                Path::new(&args.page_path),
+                args.title.as_deref(),
+                args.draft,
+            )?;
+            println!("Created {:?}", args.page_path);
+            Ok(())
+        }
+        Some(Command::Lsp(args)) => run_lsp(&Options::from(&args.rules)),
+    }
+}
+
+/// Prints the results of a run and exits with the code for the most severe
+/// thing found
+fn finish(report: &Report, output: &OutputArgs) -> anyhow::Result<()> {
+    println!("Run Completed");
+    let stats = report.stats();
+    if output.stats_json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(stats).context("Failed to serialize stats")?
+        );
+    } else {
+        println!("File Stats: {stats}");
+    }
+    if output.list_suppressions {
+        println!("Suppressions:");
+        for suppression in report.suppressions() {
+            println!("  {suppression}");
+        }
+    }
+    if !report.fixed_baseline_entries().is_empty() {
+        println!("Fixed (can be removed from the baseline using --update-baseline):");
+        for entry in report.fixed_baseline_entries() {
+            println!("  {entry}");
+        }
+    }
+
+    match report.exit_code() {
+        1 => {
+            if !report.failures().is_empty() {
+                println!("Failures:");
+                for failure in report.failures() {
+                    println!("  {}", ErrorChain(failure));
+                }
+            }
+            let errors = new_diagnostics_with(report, Severity::Error);
+            if !errors.is_empty() {
+                println!("Errors:");
+                for diagnostic in errors.iter() {
+                    println!("  {diagnostic}");
+                }
+            }
+            let msg = format!(
+                "Run FAILED! {} error(s)",
+                report.failures().len() + errors.len()
+            );
+            error!("{msg}");
+            bail!("{msg}");
+        }
+        2 => {
             println!("{} files would have been changed", stats.changed());
             std::process::exit(2);
         }
-    } else {
-        let msg = format!("Run FAILED! {} error(s)", stats.errors());
-        error!("{msg}");
-        bail!("{msg}");
+        3 => {
+            let warnings = new_diagnostics_with(report, Severity::Warning).len();
+            let baselined = report.diagnostics().len() - report.new_diagnostics().count();
+            println!(
+                "There {} {} {}warnings{}",
+                if warnings == 1 { "is" } else { "are" },
+                warnings,
+                if output.baseline.is_some() {
+                    "new "
+                } else {
+                    ""
+                },
+                if baselined > 0 {
+                    format!(" ({baselined} in baseline)")
+                } else {
+                    String::new()
+                }
+            );
+            std::process::exit(3);
+        }
+        _ => {}
     }
-    if !cli.ignore_seo && stats.seo_warnings() > 0 {
+    Ok(())
+}
+
+/// Prints every problem found grouped by file along with the statistics
+fn print_report(report: &Report, json: bool) -> anyhow::Result<()> {
+    if json {
+        let diagnostics: Vec<_> = report
+            .diagnostics()
+            .iter()
+            .map(|x| {
+                serde_json::json!({
+                    "path": x.path(),
+                    "rule": x.rule().id(),
+                    "severity": x.severity().name(),
+                    "message": x.message(),
+                })
+            })
+            .collect();
+        let failures: Vec<_> = report
+            .failures()
+            .iter()
+            .map(|x| ErrorChain(x).to_string())
+            .collect();
+        let value = serde_json::json!({
+            "diagnostics": diagnostics,
+            "failures": failures,
+            "stats": report.stats(),
+        });
         println!(
-            "There {} {} SEO warnings",
-            if stats.seo_warnings() == 1 {
-                "is"
-            } else {
-                "are"
-            },
-            stats.seo_warnings()
+            "{}",
+            serde_json::to_string_pretty(&value).context("Failed to serialize report")?
         );
-        std::process::exit(3);
+        return Ok(());
+    }
+    let mut by_path: BTreeMap<&Path, Vec<&Diagnostic>> = BTreeMap::new();
+    for diagnostic in report.diagnostics() {
+        by_path
+            .entry(diagnostic.path())
+            .or_default()
+            .push(diagnostic);
+    }
+    for (path, diagnostics) in by_path {
+        println!("{path:?}");
+        for diagnostic in diagnostics {
+            println!(
+                "  {:<7} [{}] {}",
+                diagnostic.severity().name(),
+                diagnostic.rule(),
+                diagnostic.message()
+            );
+        }
+    }
+    if !report.failures().is_empty() {
+        println!("Failures:");
+        for failure in report.failures() {
+            println!("  {}", ErrorChain(failure));
+        }
     }
+    println!("File Stats: {}", report.stats());
+    Ok(())
+}
+
+/// Prints the description of the rule with the id `rule` (or a summary of all
+/// the rules)
+fn explain(rule: Option<&str>) -> anyhow::Result<()> {
+    let Some(rule) = rule else {
+        for rule in Rule::ALL {
+            let seo = if rule.is_seo() { " (SEO)" } else { "" };
+            println!(
+                "{:<24} {:<7}{seo}",
+                rule.id(),
+                rule.default_severity().name()
+            );
+        }
+        println!("\nRun `explain <RULE>` for the details of a rule");
+        return Ok(());
+    };
+    let rule = rule.parse::<Rule>().map_err(anyhow::Error::msg)?;
+    println!("{rule}{}", if rule.is_seo() { " (SEO)" } else { "" });
+    println!("Default severity: {}\n", rule.default_severity());
+    println!("{}\n", rule.description());
+    println!("Change the severity in `{PROJECT_CONFIG_FILE}`:");
+    println!("    [rules]\n    {rule} = \"info\"");
+    println!("Or turn it off for a page (or section) in its front matter:");
+    println!("    # abridge-helper: ignore {rule} reason=\"...\"");
+    Ok(())
+}
+
+/// Problems found that are not in the baseline with exactly `severity`
+fn new_diagnostics_with(report: &Report, severity: Severity) -> Vec<&Diagnostic> {
+    report
+        .new_diagnostics()
+        .filter(|x| x.severity() == severity)
+        .collect()
+}
+
+/// Reads the page from stdin and writes the result to stdout. Everything else
+/// goes to stderr to keep stdout usable by editors.
+fn filter(options: &Options, page_path: &Path) -> anyhow::Result<()> {
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .context("Failed to read page from stdin")?;
+    let (output, report) = run_filter(options, page_path, &input)?;
+    std::io::stdout()
+        .write_all(output.as_bytes())
+        .context("Failed to write page to stdout")?;
+    eprintln!("File Stats: {}", report.stats());
     Ok(())
 }
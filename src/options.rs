@@ -0,0 +1,369 @@
+//! Settings that control a run independent of how they were provided
+
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use toml_edit::Table;
+
+use crate::cli::{CheckArgs, Cli, FixArgs, OutputArgs, ReportArgs, RuleArgs, SiteArgs, WriteArgs};
+
+use crate::{
+    DraftPolicy, Result, SymlinkPolicy,
+    processing::is_draft,
+    project_config::ProjectConfig,
+    rule::{Rule, Severity},
+};
+
+/// Used if no duplicate threshold is set
+const DEFAULT_DUPLICATE_THRESHOLD: u8 = 90;
+
+/// Used if no image size limit is set
+const DEFAULT_MAX_IMAGE_SIZE_KB: u64 = 500;
+
+/// Settings for a run
+///
+/// Built with [`Options::new`] and then adjusted using the setters which all
+/// default to `false` (or `None`).
+///
+/// ```
+/// # use zola_abridge_helper::Options;
+/// let options = Options::new("path/to/site")
+///     .check_only(true)
+///     .ignore_missing_description(true);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct Options {
+    pub(crate) root_path: PathBuf,
+    pub(crate) ignore_seo: bool,
+    pub(crate) ignore_missing_description: bool,
+    pub(crate) check_only: bool,
+    pub(crate) allow_dirty: bool,
+    pub(crate) strict_write: bool,
+    pub(crate) backup: bool,
+    pub(crate) baseline: Option<PathBuf>,
+    pub(crate) update_baseline: bool,
+    pub(crate) include_globs: Vec<String>,
+    pub(crate) exclude_globs: Vec<String>,
+    pub(crate) no_gitignore: bool,
+    pub(crate) hidden: bool,
+    pub(crate) set_updated: bool,
+    symlinks: Option<SymlinkPolicy>,
+    drafts: Option<DraftPolicy>,
+    /// Set on the options used for a draft page
+    is_draft: bool,
+    severities: BTreeMap<Rule, Severity>,
+    duplicate_threshold: Option<u8>,
+    max_image_size_kb: Option<u64>,
+}
+
+impl Options {
+    /// Creates options for the zola site at `root_path` with everything else
+    /// set to the defaults
+    pub fn new(root_path: impl Into<PathBuf>) -> Self {
+        Self {
+            root_path: root_path.into(),
+            ignore_seo: false,
+            ignore_missing_description: false,
+            check_only: false,
+            allow_dirty: false,
+            strict_write: false,
+            backup: false,
+            baseline: None,
+            update_baseline: false,
+            include_globs: Vec::new(),
+            exclude_globs: Vec::new(),
+            no_gitignore: false,
+            hidden: false,
+            set_updated: false,
+            symlinks: None,
+            drafts: None,
+            is_draft: false,
+            severities: BTreeMap::new(),
+            duplicate_threshold: None,
+            max_image_size_kb: None,
+        }
+    }
+
+    /// SEO rules are treated as [`Severity::Info`] at most (See
+    /// [`RuleArgs::ignore_seo`])
+    pub fn ignore_seo(mut self, value: bool) -> Self {
+        self.ignore_seo = value;
+        self
+    }
+
+    /// Missing descriptions are not reported, same as setting
+    /// [`Rule::DescriptionMissing`] to [`Severity::Off`] (See
+    /// [`RuleArgs::ignore_missing_description`])
+    pub fn ignore_missing_description(mut self, value: bool) -> Self {
+        self.ignore_missing_description = value;
+        self
+    }
+
+    /// No files are modified, changes that would have been made are only
+    /// reported (See [`Cli::should_check_only`])
+    pub fn check_only(mut self, value: bool) -> Self {
+        self.check_only = value;
+        self
+    }
+
+    /// Allows changes even if there are dirty files in version control (See
+    /// [`WriteArgs::allow_dirty`])
+    pub fn allow_dirty(mut self, value: bool) -> Self {
+        self.allow_dirty = value;
+        self
+    }
+
+    /// Refuses to write files if anything outside of the front matter would
+    /// change (See [`WriteArgs::strict_write`])
+    pub fn strict_write(mut self, value: bool) -> Self {
+        self.strict_write = value;
+        self
+    }
+
+    /// Keeps a `.orig` copy of each file before it is changed (See
+    /// [`WriteArgs::backup`])
+    pub fn backup(mut self, value: bool) -> Self {
+        self.backup = value;
+        self
+    }
+
+    /// File with the known problems (of any rule and severity) that should
+    /// not fail the run (See [`OutputArgs::baseline`])
+    pub fn baseline(mut self, value: Option<PathBuf>) -> Self {
+        self.baseline = value;
+        self
+    }
+
+    /// Writes the problems currently found to the baseline file instead of
+    /// reading it (See [`OutputArgs::update_baseline`])
+    pub fn update_baseline(mut self, value: bool) -> Self {
+        self.update_baseline = value;
+        self
+    }
+
+    /// Only files matching at least one of these globs are processed (See
+    /// [`SiteArgs::include`])
+    pub fn include_globs(mut self, value: Vec<String>) -> Self {
+        self.include_globs = value;
+        self
+    }
+
+    /// Files matching any of these globs are not processed (See
+    /// [`SiteArgs::exclude`])
+    pub fn exclude_globs(mut self, value: Vec<String>) -> Self {
+        self.exclude_globs = value;
+        self
+    }
+
+    /// Processes files even if they are ignored by git (See
+    /// [`SiteArgs::no_gitignore`])
+    pub fn no_gitignore(mut self, value: bool) -> Self {
+        self.no_gitignore = value;
+        self
+    }
+
+    /// Processes hidden files and folders (See [`SiteArgs::hidden`])
+    pub fn hidden(mut self, value: bool) -> Self {
+        self.hidden = value;
+        self
+    }
+
+    /// How symbolic links are treated (See [`SiteArgs::symlinks`])
+    pub fn symlinks(mut self, value: Option<SymlinkPolicy>) -> Self {
+        self.symlinks = value;
+        self
+    }
+
+    /// How pages with `draft = true` are treated (See [`RuleArgs::drafts`])
+    pub fn drafts(mut self, value: Option<DraftPolicy>) -> Self {
+        self.drafts = value;
+        self
+    }
+
+    /// Sets `updated` on pages from the git history (See
+    /// [`SiteArgs::set_updated`])
+    pub fn set_updated(mut self, value: bool) -> Self {
+        self.set_updated = value;
+        self
+    }
+
+    /// Sets the severity of `rule` (Takes precedence over the project config)
+    pub fn severity(mut self, rule: Rule, severity: Severity) -> Self {
+        self.severities.insert(rule, severity);
+        self
+    }
+
+    /// Percentage similarity at which titles and descriptions are reported as
+    /// duplicates (Takes precedence over the project config). Values above
+    /// 100 are treated as 100.
+    pub fn duplicate_threshold(mut self, value: u8) -> Self {
+        self.duplicate_threshold = Some(value.min(100));
+        self
+    }
+
+    /// Size in kilobytes above which referenced images are reported (Takes
+    /// precedence over the project config)
+    pub fn max_image_size_kb(mut self, value: u64) -> Self {
+        self.max_image_size_kb = Some(value);
+        self
+    }
+
+    /// Root folder of the zola site
+    pub fn root_path(&self) -> &Path {
+        &self.root_path
+    }
+
+    pub(crate) fn get_symlinks(&self) -> SymlinkPolicy {
+        self.symlinks.unwrap_or_default()
+    }
+
+    pub(crate) fn get_drafts(&self) -> DraftPolicy {
+        self.drafts.unwrap_or_default()
+    }
+
+    pub(crate) fn get_duplicate_threshold(&self) -> u8 {
+        self.duplicate_threshold
+            .unwrap_or(DEFAULT_DUPLICATE_THRESHOLD)
+    }
+
+    pub(crate) fn get_max_image_size_kb(&self) -> u64 {
+        self.max_image_size_kb.unwrap_or(DEFAULT_MAX_IMAGE_SIZE_KB)
+    }
+
+    /// The severity `rule` is checked at taking into account all the settings
+    pub fn rule_severity(&self, rule: Rule) -> Severity {
+        let result = self
+            .severities
+            .get(&rule)
+            .copied()
+            .unwrap_or_else(|| rule.default_severity());
+        let result = if rule == Rule::DescriptionMissing && self.ignore_missing_description {
+            Severity::Off
+        } else if rule.is_seo() && self.ignore_seo {
+            result.min(Severity::Info)
+        } else {
+            result
+        };
+        if self.is_draft {
+            self.draft_severity(rule, result)
+        } else {
+            result
+        }
+    }
+
+    /// The severity `rule` is checked at for a draft given the `severity` it
+    /// is checked at for published pages
+    pub(crate) fn draft_severity(&self, rule: Rule, severity: Severity) -> Severity {
+        match self.get_drafts() {
+            DraftPolicy::Check => severity,
+            DraftPolicy::Skip => Severity::Off,
+            DraftPolicy::NoFail => severity.min(Severity::Info),
+            DraftPolicy::FixOnly if rule.is_seo() => Severity::Off,
+            DraftPolicy::FixOnly => severity,
+        }
+    }
+
+    /// The options to use for the page with the front matter in `table`
+    /// (Applies the draft policy if the page is a draft)
+    pub(crate) fn for_page(&self, table: &Table) -> Cow<'_, Self> {
+        if is_draft(table) && self.get_drafts() != DraftPolicy::Check {
+            let mut result = self.clone();
+            result.is_draft = true;
+            Cow::Owned(result)
+        } else {
+            Cow::Borrowed(self)
+        }
+    }
+
+    /// Adds the settings from the project config of the site at `root_path`
+    /// that have not been set already (Patterns are added to those already
+    /// set)
+    pub(crate) fn with_project_config(mut self, root_path: &Path) -> Result<Self> {
+        let config = ProjectConfig::load(root_path)?;
+        for (rule, severity) in config.severities {
+            self.severities.entry(rule).or_insert(severity);
+        }
+        self.duplicate_threshold = self.duplicate_threshold.or(config.duplicate_threshold);
+        self.max_image_size_kb = self.max_image_size_kb.or(config.max_image_size_kb);
+        self.include_globs.extend(config.include_globs);
+        self.exclude_globs.extend(config.exclude_globs);
+        self.no_gitignore |= config.no_gitignore;
+        self.hidden |= config.hidden;
+        self.symlinks = self.symlinks.or(config.symlinks);
+        self.drafts = self.drafts.or(config.drafts);
+        Ok(self)
+    }
+
+    fn with_rule_args(self, args: &RuleArgs) -> Self {
+        self.ignore_seo(args.ignore_seo)
+            .ignore_missing_description(args.ignore_missing_description)
+            .drafts(args.drafts)
+    }
+
+    fn with_write_args(self, args: &WriteArgs) -> Self {
+        self.allow_dirty(args.allow_dirty)
+            .strict_write(args.strict_write)
+            .backup(args.backup)
+    }
+
+    fn with_output_args(self, args: &OutputArgs) -> Self {
+        self.baseline(args.baseline.as_ref().map(PathBuf::from))
+            .update_baseline(args.update_baseline)
+    }
+}
+
+impl From<&RuleArgs> for Options {
+    /// Uses the current folder as the root path (For uses like the language
+    /// server where the site is found from the file being checked)
+    fn from(args: &RuleArgs) -> Self {
+        Options::new(".").with_rule_args(args)
+    }
+}
+
+impl From<&SiteArgs> for Options {
+    fn from(args: &SiteArgs) -> Self {
+        Options::new(&args.root_path)
+            .with_rule_args(&args.rules)
+            .include_globs(args.include.clone())
+            .exclude_globs(args.exclude.clone())
+            .no_gitignore(args.no_gitignore)
+            .hidden(args.hidden)
+            .symlinks(args.symlinks)
+            .set_updated(args.set_updated)
+    }
+}
+
+impl From<&Cli> for Options {
+    fn from(cli: &Cli) -> Self {
+        Options::from(&cli.site)
+            .check_only(cli.should_check_only)
+            .with_write_args(&cli.write)
+            .with_output_args(&cli.output)
+    }
+}
+
+impl From<&CheckArgs> for Options {
+    fn from(args: &CheckArgs) -> Self {
+        Options::from(&args.site)
+            .check_only(true)
+            .with_output_args(&args.output)
+    }
+}
+
+impl From<&FixArgs> for Options {
+    fn from(args: &FixArgs) -> Self {
+        Options::from(&args.site)
+            .with_write_args(&args.write)
+            .with_output_args(&args.output)
+    }
+}
+
+impl From<&ReportArgs> for Options {
+    fn from(args: &ReportArgs) -> Self {
+        Options::from(&args.site).check_only(true)
+    }
+}
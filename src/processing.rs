@@ -1,100 +1,388 @@
-use crate::{PREFERRED_RANGE, cli::Cli, section_info::SectionInfo, stats::Stats};
+use crate::{
+    DraftPolicy, Options, PREFERRED_RANGE,
+    dates::{self, find_date_issues},
+    error::{Error, ErrorChain, Result},
+    file_filter::{FileFilter, Visited},
+    report::Report,
+    rule::{Rule, Severity},
+    section_info::{SectionInfo, find_setting_issues},
+    stats::RuleStats,
+    suppression::{Suppression, parse_suppressions},
+};
 
-use anyhow::Context;
 use std::{
+    borrow::Cow,
+    collections::BTreeSet,
     fs::{self, DirEntry},
     path::Path,
 };
-use toml_edit::DocumentMut;
-use tracing::{error, trace, warn};
+use toml_edit::{DocumentMut, Table};
+use tracing::{error, info, trace, warn};
 
-use self::file_data::FileData;
+pub use self::file_data::FileData;
+pub(crate) use self::file_data::{front_matter_span, set_series, set_tags};
 mod file_data;
 
-pub fn validate_zola_config(path: &Path, cli: &Cli) -> anyhow::Result<Stats> {
-    let contents = fs::read_to_string(path)
-        .with_context(|| format!("failed to read config at: {:?}", path))?;
+pub fn validate_zola_config(path: &Path, options: &Options) -> Result<Report> {
+    let contents = fs::read_to_string(path).map_err(|source| Error::ConfigUnreadable {
+        path: path.to_path_buf(),
+        source,
+    })?;
     let toml_doc = contents
         .parse::<DocumentMut>()
-        .with_context(|| format!("Failed to parse config at: {path:?}"))?;
-    let result = check_description(&toml_doc, cli, path);
+        .map_err(|e| Error::invalid_toml(path, e))?;
+    let result = check_description(&toml_doc, options, path, &BTreeSet::new());
     Ok(result)
 }
 
-fn check_description(toml_doc: &DocumentMut, cli: &Cli, path: &Path) -> Stats {
-    let mut result = Stats::new();
-    let Some(description) = toml_doc.get("description") else {
-        if !cli.ignore_missing_description {
-            result.inc_seo_warnings();
-            warn!("(SEO) failed to find description in file at: {path:?}");
+/// Problems that can be found with a description
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DescriptionIssue {
+    Missing,
+    NotString,
+    /// Holds the actual length of the description
+    OutsidePreferredRange(usize),
+}
+
+impl std::fmt::Display for DescriptionIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DescriptionIssue::Missing => write!(f, "(SEO) failed to find description"),
+            DescriptionIssue::NotString => write!(f, "(SEO) failed description is not string"),
+            DescriptionIssue::OutsidePreferredRange(len) => write!(
+                f,
+                "(SEO) description outside of the preferred range. Actual Length {len}. Preferred range: {PREFERRED_RANGE:?}"
+            ),
         }
-        return result;
+    }
+}
+
+impl DescriptionIssue {
+    /// Id of the rule that found the issue
+    pub(crate) fn rule(&self) -> Rule {
+        match self {
+            DescriptionIssue::Missing => Rule::DescriptionMissing,
+            DescriptionIssue::NotString => Rule::DescriptionNotString,
+            DescriptionIssue::OutsidePreferredRange(_) => Rule::DescriptionLength,
+        }
+    }
+}
+
+/// Returns the problem with the description in `table` if any (Does not take
+/// into account any settings)
+pub(crate) fn find_description_issue(table: &Table) -> Option<DescriptionIssue> {
+    let Some(description) = table.get("description") else {
+        return Some(DescriptionIssue::Missing);
     };
     let Some(description) = description.as_str() else {
-        result.inc_seo_warnings();
-        warn!(
-            "(SEO) failed description is not string in file at: {path:?}. Value found: {description:?}"
-        );
+        return Some(DescriptionIssue::NotString);
+    };
+    if is_description_length_in_preferred_range(description) {
+        None
+    } else {
+        Some(DescriptionIssue::OutsidePreferredRange(description.len()))
+    }
+}
+
+/// Checks if the `transparent` value is set and is a boolean (required for
+/// sections)
+pub(crate) fn is_transparent_set(table: &Table) -> bool {
+    table.get("transparent").is_some_and(|x| x.is_bool())
+}
+
+/// True if the page with the front matter in `table` is a draft
+pub(crate) fn is_draft(table: &Table) -> bool {
+    table.get("draft").and_then(|x| x.as_bool()) == Some(true)
+}
+
+fn check_description(
+    toml_doc: &DocumentMut,
+    options: &Options,
+    path: &Path,
+    suppressed: &BTreeSet<Rule>,
+) -> Report {
+    let mut result = Report::new();
+    let Some(issue) = find_description_issue(toml_doc) else {
         return result;
     };
-    if !is_description_length_in_preferred_range(description) {
-        result.inc_seo_warnings();
-        if !cli.ignore_seo {
-            // TODO 4: Find a way to ignore description length on a per file basis. Another
-            // field in "extra"?
-            warn!(
-                "(SEO) description outside of the preferred range. Actual Length {}. Preferred range: {:?}, Path: {path:?}",
-                description.len(),
-                PREFERRED_RANGE
-            );
+    let rule = issue.rule();
+    let severity = options.rule_severity(rule);
+    if severity == Severity::Off || suppressed.contains(&rule) {
+        return result;
+    }
+    let checked_value = toml_doc
+        .get("description")
+        .map(|x| x.as_str().map_or_else(|| x.to_string(), str::to_string))
+        .unwrap_or_default();
+    let rules = result.stats_mut().rules_mut();
+    match issue {
+        DescriptionIssue::Missing => {
+            rules.description_missing = rules.description_missing.saturating_add(1);
+        }
+        DescriptionIssue::NotString => {}
+        DescriptionIssue::OutsidePreferredRange(len) => {
+            if len < *PREFERRED_RANGE.start() {
+                rules.description_too_short = rules.description_too_short.saturating_add(1);
+            } else {
+                rules.description_too_long = rules.description_too_long.saturating_add(1);
+            }
+        }
+    }
+    log_diagnostic(severity, rule, &format!("{issue} in file at: {path:?}"));
+    result.add_diagnostic(path, rule, severity, issue.to_string(), &checked_value);
+    result
+}
+
+/// Reports the `disable_check_*` settings in `extra` that are ignored because
+/// they are not booleans or are misspelled
+pub(crate) fn check_settings(
+    table: &Table,
+    options: &Options,
+    path: &Path,
+    suppressed: &BTreeSet<Rule>,
+) -> Report {
+    let mut result = Report::new();
+    let rule = Rule::InvalidSetting;
+    let severity = options.rule_severity(rule);
+    if severity == Severity::Off || suppressed.contains(&rule) {
+        return result;
+    }
+    for issue in find_setting_issues(table) {
+        log_diagnostic(severity, rule, &format!("{issue} in file at: {path:?}"));
+        result.add_diagnostic(path, rule, severity, issue.to_string(), issue.key());
+    }
+    result
+}
+
+/// Reports dates that are not valid, `updated` earlier than `date` and dates
+/// in the future (unless the page is a draft)
+pub(crate) fn check_dates(
+    table: &Table,
+    options: &Options,
+    path: &Path,
+    suppressed: &BTreeSet<Rule>,
+) -> Report {
+    let mut result = Report::new();
+    for issue in find_date_issues(table, dates::now()) {
+        let rule = issue.rule();
+        let severity = options.rule_severity(rule);
+        if severity == Severity::Off || suppressed.contains(&rule) {
+            continue;
         }
+        log_diagnostic(severity, rule, &format!("{issue} in file at: {path:?}"));
+        result.add_diagnostic(path, rule, severity, issue.to_string(), issue.value());
     }
     result
 }
 
+/// Reports a page that does not have the key its section sorts pages by (or
+/// it has the wrong type)
+pub(crate) fn check_sort_key(
+    table: &Table,
+    options: &Options,
+    path: &Path,
+    section_info: Option<&SectionInfo>,
+    suppressed: &BTreeSet<Rule>,
+) -> Report {
+    let mut result = Report::new();
+    let rule = Rule::SortKeyMissing;
+    let severity = options.rule_severity(rule);
+    let Some(section_info) = section_info else {
+        return result;
+    };
+    if severity == Severity::Off || suppressed.contains(&rule) {
+        return result;
+    }
+    let Some(issue) = section_info
+        .sort_by
+        .and_then(|sort_by| sort_by.find_issue(table))
+    else {
+        return result;
+    };
+    log_diagnostic(severity, rule, &format!("{issue} in file at: {path:?}"));
+    result.add_diagnostic(path, rule, severity, issue.to_string(), issue.key());
+    result
+}
+
+/// Logs a problem found by `rule` at the level matching `severity`
+pub(crate) fn log_diagnostic(severity: Severity, rule: Rule, message: &str) {
+    match severity {
+        Severity::Off => {}
+        Severity::Info => info!("[{rule}] {message}"),
+        Severity::Warning => warn!("[{rule}] {message}"),
+        Severity::Error => error!("[{rule}] {message}"),
+    }
+}
+
+/// Walks `root_path` processing the files found
+///
+/// Failures are recorded in the returned report against the path they occurred
+/// on and the walk continues with the remaining paths
 pub fn check_path(
     root_path: &Path,
-    cli: &Cli,
+    options: &Options,
+    filter: &FileFilter,
+    visited: &mut Visited,
     section_info: Option<&SectionInfo>,
-) -> anyhow::Result<Stats> {
-    let mut result = Stats::new();
-    if root_path.is_file() {
-        match process_file(root_path, cli, section_info)
-            .with_context(|| format!("Processing failed for: {root_path:?}"))
-        {
-            Ok(stats) => result += stats,
-            Err(e) => {
-                error!("{e:?}");
-                result.inc_errors();
-            }
+) -> Report {
+    let mut result = Report::new();
+    match visited.enter(root_path, options.get_symlinks()) {
+        Ok(true) => {}
+        Ok(false) => {
+            result.stats_mut().inc_skipped();
+            return result;
+        }
+        Err(e) => {
+            record_failure(&mut result, e);
+            return result;
         }
+    }
+    if root_path.is_dir() {
+        result += check_dir(root_path, options, filter, visited, section_info);
+        visited.leave();
     } else {
-        let mut dir_entries = fs::read_dir(root_path)
-            .with_context(|| format!("Failed to read directory: {root_path:?}"))?
-            .map(|x| x.with_context(|| format!("Failed to extract a DirEntry in {root_path:?}")))
-            .collect::<anyhow::Result<Vec<DirEntry>>>()?;
-        let mut sub_section_info = None;
-        if let Some((section_info, sec_result)) = extract_section_info(&mut dir_entries)? {
-            sub_section_info = Some(section_info);
-            result += sec_result;
+        match process_file(root_path, options, section_info) {
+            Ok(stats) => result += stats,
+            Err(e) => record_failure(&mut result, e),
         }
-        let info = if sub_section_info.is_some() {
+    }
+
+    result
+}
+
+/// Walks the entries of the folder at `path` (See [`check_path`])
+fn check_dir(
+    path: &Path,
+    options: &Options,
+    filter: &FileFilter,
+    visited: &mut Visited,
+    section_info: Option<&SectionInfo>,
+) -> Report {
+    let mut result = Report::new();
+    let mut dir_entries = match read_included_entries(path, filter) {
+        Ok(dir_entries) => dir_entries,
+        Err(e) => {
+            record_failure(&mut result, e);
+            return result;
+        }
+    };
+    let sub_section_info;
+    let mut is_section = true;
+    let info = match extract_section_info(&mut dir_entries, options) {
+        Ok(Some((section_info, sec_result))) => {
+            result += sec_result;
+            sub_section_info = Some(section_info);
             sub_section_info.as_ref()
-        } else {
-            // Use same section name for subfolder that are not sections on their own
+        }
+        // Use same section name for subfolder that are not sections on their own
+        Ok(None) => {
+            is_section = false;
             section_info
-        };
+        }
+        Err(e) => {
+            // Pages are still checked but not updated as the section is unknown
+            record_failure(&mut result, e);
+            None
+        }
+    };
+    let bundle_index = dir_entries
+        .iter()
+        .position(|entry| entry.file_name() == "index.md" && entry.path().is_file());
+    if !is_section && let Some(bundle_index) = bundle_index {
+        // A page bundle, only `index.md` is a page and the rest are assets
+        let index_path = dir_entries.remove(bundle_index).path();
+        result += check_path(&index_path, options, filter, visited, info);
         for entry in dir_entries {
-            result += check_path(&entry.path(), cli, info)?;
+            result += check_bundle_files(&entry.path(), options, filter, visited, info);
         }
+        return result;
     }
+    for entry in dir_entries {
+        result += check_path(&entry.path(), options, filter, visited, info);
+    }
+    result
+}
 
+/// Reads the entries of the folder at `path` that are included by `filter`
+///
+/// Entries are sorted by name so that the walk is done in the same order
+/// every run (The first path to a file wins if it can be reached through
+/// more than one)
+fn read_included_entries(path: &Path, filter: &FileFilter) -> Result<Vec<DirEntry>> {
+    let read_dir =
+        fs::read_dir(path).map_err(|e| Error::io(path, "Failed to read directory", e))?;
+    let mut result = Vec::new();
+    for entry in read_dir {
+        let entry = entry.map_err(|e| Error::io(path, "Failed to extract a DirEntry", e))?;
+        if filter.is_included(&entry.path()) {
+            result.push(entry);
+        } else {
+            trace!("(Excluded)    {:?}", entry.path());
+        }
+    }
+    result.sort_by_key(|entry| entry.file_name());
     Ok(result)
 }
 
+/// Counts the assets of a page bundle in `path` (a file or folder in the
+/// bundle) and reports any markdown files as they are not built as pages
+fn check_bundle_files(
+    path: &Path,
+    options: &Options,
+    filter: &FileFilter,
+    visited: &mut Visited,
+    section_info: Option<&SectionInfo>,
+) -> Report {
+    let mut result = Report::new();
+    match visited.enter(path, options.get_symlinks()) {
+        Ok(true) => {}
+        Ok(false) => {
+            result.stats_mut().inc_skipped();
+            return result;
+        }
+        Err(e) => {
+            record_failure(&mut result, e);
+            return result;
+        }
+    }
+    if path.is_dir() {
+        match read_included_entries(path, filter) {
+            Ok(dir_entries) => {
+                for entry in dir_entries {
+                    result +=
+                        check_bundle_files(&entry.path(), options, filter, visited, section_info);
+                }
+            }
+            Err(e) => record_failure(&mut result, e),
+        }
+        visited.leave();
+    } else if path.extension().is_some_and(|ext| ext == "md") {
+        result.stats_mut().inc_skipped();
+        let rule = Rule::BundleMarkdown;
+        let severity = options.rule_severity(rule);
+        if severity == Severity::Off || section_info.is_some_and(|info| info.is_suppressed(rule)) {
+            return result;
+        }
+        let message = "markdown file in a page bundle is not built as a page (Only `index.md` is)";
+        log_diagnostic(severity, rule, &format!("{message}. In file at: {path:?}"));
+        result.add_diagnostic(path, rule, severity, message.to_string(), "");
+    } else {
+        result.stats_mut().inc_bundle_assets();
+        trace!("(Asset)       {path:?}");
+    }
+    result
+}
+
+/// Logs the error and records it in `report`
+fn record_failure(report: &mut Report, e: Error) {
+    error!("{}", ErrorChain(&e));
+    report.add_failure(e);
+}
+
 fn extract_section_info(
     dir_entries: &mut Vec<DirEntry>,
-) -> anyhow::Result<Option<(SectionInfo, Stats)>> {
+    options: &Options,
+) -> Result<Option<(SectionInfo, Report)>> {
     // Check if there is a file with section information in the folder
     let section_idx = dir_entries.iter().enumerate().find_map(|(i, entry)| {
         if entry.file_name() == "_index.md" {
@@ -106,9 +394,7 @@ fn extract_section_info(
                         None
                     }
                 }
-                Err(e) => Some(Err(e).with_context(|| {
-                    format!("failed to get file type for: {:?}", entry.file_name())
-                })),
+                Err(e) => Some(Err(Error::io(&entry.path(), "Failed to get file type", e))),
             }
         } else {
             None
@@ -121,48 +407,292 @@ fn extract_section_info(
     };
 
     let section_idx = section_idx?; // Return if there was an error getting section info
-    let section_dir_entry = dir_entries.swap_remove(section_idx);
-    let result = FileData::new_from_path(&section_dir_entry.path())?.extract_section_info()?;
+    let section_dir_entry = dir_entries.remove(section_idx);
+    let result =
+        FileData::new_from_path(&section_dir_entry.path())?.extract_section_info(options)?;
     Ok(Some(result))
 }
 
 fn process_file(
     path: &Path,
-    cli: &Cli,
+    options: &Options,
     section_info: Option<&SectionInfo>,
-) -> anyhow::Result<Stats> {
-    let mut result = Stats::new();
+) -> Result<Report> {
+    let mut result = Report::new();
     if !should_skip_file(path) {
         let mut data = FileData::new_from_path(path)?;
-        let section_info = if let Some(info) = section_info {
-            let toml_doc = data.front_matter_as_toml()?;
-            Some(info.load_settings(&toml_doc))
-        } else {
-            None
-        };
-        let section_info = section_info.as_deref();
-        result += data.check_description(cli, section_info)?;
-        data.update_series_and_tags(section_info)
-            .context("failed to update tags and/or series")?;
+        let toml_doc = data.front_matter_as_toml()?;
+        let options = &*options.for_page(&toml_doc);
+        if is_draft(&toml_doc) && options.get_drafts() == DraftPolicy::Skip {
+            result.add_site_file(data.to_site_file(section_info, BTreeSet::new())?);
+            result.stats_mut().inc_skipped();
+            trace!("(Draft)       {path:?}");
+            return Ok(result);
+        }
+        result += check_and_update(&mut data, options, section_info)?;
         if data.is_changed() {
-            result.inc_changed();
-            if cli.should_check_only {
+            if options.check_only {
+                result.add_changed(path);
                 warn!("(Change here) {path:?}");
+                let mut pending_severity = Severity::Off;
+                for (rule, severity) in changed_rules(result.stats().rules(), options) {
+                    if severity == Severity::Error {
+                        // Listed and counted with the errors so the run says
+                        // why it failed
+                        let message = "file would be changed to fix it".to_string();
+                        log_diagnostic(severity, rule, &format!("{message}. In file at: {path:?}"));
+                        result.add_diagnostic(path, rule, severity, message, data.front_matter());
+                    }
+                    pending_severity = pending_severity.max(severity.min(Severity::Warning));
+                }
+                result.add_pending_change(pending_severity);
+            } else if let Err(e) = write_page(&data, section_info, options, result.stats().rules())
+            {
+                // Kept with what was already found for the page
+                record_failure(&mut result, e);
             } else {
-                data.write().context("failed to write to file")?;
+                result.add_changed(path);
                 trace!("(Changed)     {path:?}");
             }
         } else {
-            result.inc_not_changed();
+            result.stats_mut().inc_not_changed();
             trace!("(Not Changed) {path:?}");
         };
     } else {
-        result.inc_skipped();
+        result.stats_mut().inc_skipped();
         trace!("(Skipped)     {path:?}");
     }
     Ok(result)
 }
 
+/// Applies the checks and fixes to a page that has already been loaded
+fn check_and_update(
+    data: &mut FileData,
+    options: &Options,
+    section_info: Option<&SectionInfo>,
+) -> Result<Report> {
+    let page_settings = load_page_settings(data, section_info, options)?;
+    let page_settings = page_settings.as_deref();
+    let mut result = Report::new();
+    let (suppressions, invalid) = parse_suppressions(data.path(), data.front_matter());
+    let mut suppressed = page_settings
+        .map(|info| info.suppressed.clone())
+        .unwrap_or_default();
+    suppressed.extend(suppressions.iter().map(|x| x.rule()));
+    result.add_suppressions(suppressions);
+    for e in invalid {
+        record_failure(&mut result, e);
+    }
+    let toml_doc = data.front_matter_as_toml()?;
+    result += check_settings(&toml_doc, options, data.path(), &suppressed);
+    result += check_sort_key(&toml_doc, options, data.path(), page_settings, &suppressed);
+    result += check_dates(&toml_doc, options, data.path(), &suppressed);
+    result += data.check_description(options, page_settings, &suppressed)?;
+    result += data.update_series_and_tags(page_settings)?;
+    if options.set_updated
+        && options.rule_severity(Rule::UpdatedOutdated) != Severity::Off
+        && !suppressed.contains(&Rule::UpdatedOutdated)
+    {
+        result += data.update_updated()?;
+    }
+    result.add_site_file(data.to_site_file(page_settings, suppressed)?);
+    let words = data.content().split_whitespace().count() as u64;
+    let page_rules = *result.stats().rules();
+    result.stats_mut().add_page(
+        section_info.and_then(|info| Some((info.path.as_deref()?, info.section_name()))),
+        words,
+        page_rules,
+    );
+    Ok(result)
+}
+
+/// The rules that changed the file based on the counts in `rules` with the
+/// severity each is checked at
+fn changed_rules(rules: &RuleStats, options: &Options) -> Vec<(Rule, Severity)> {
+    [
+        (rules.series_set, Rule::SeriesNotSet),
+        (rules.tags_added, Rule::TagsMissingSection),
+        (rules.updated_set, Rule::UpdatedOutdated),
+    ]
+    .into_iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(_, rule)| (rule, options.rule_severity(rule)))
+    .collect()
+}
+
+/// Applies the settings set on the page (if any) to the section settings
+fn load_page_settings<'a>(
+    data: &FileData,
+    section_info: Option<&'a SectionInfo>,
+    options: &Options,
+) -> Result<Option<Cow<'a, SectionInfo>>> {
+    let Some(info) = section_info else {
+        return Ok(None);
+    };
+    let toml_doc = data.front_matter_as_toml()?;
+    let (suppressions, _) = parse_suppressions(data.path(), data.front_matter());
+    Ok(Some(page_settings(info, &toml_doc, &suppressions, options)))
+}
+
+/// Applies the settings set on the page in `table` and the page's
+/// `suppressions` to the section settings
+///
+/// Fixes for rules that are turned off (or suppressed) are disabled the same as
+/// if the page had disabled them
+pub(crate) fn page_settings<'a>(
+    info: &'a SectionInfo,
+    table: &Table,
+    suppressions: &[Suppression],
+    options: &Options,
+) -> Cow<'a, SectionInfo> {
+    let mut result = info.load_settings(table);
+    for suppression in suppressions {
+        if !result.is_suppressed(suppression.rule()) {
+            result.to_mut().suppressed.insert(suppression.rule());
+        }
+    }
+    let is_off = |rule| options.rule_severity(rule) == Severity::Off;
+    if (is_off(Rule::SeriesNotSet) || result.is_suppressed(Rule::SeriesNotSet))
+        && !result.disable_check_series
+    {
+        result.to_mut().disable_check_series = true;
+    }
+    if (is_off(Rule::TagsMissingSection) || result.is_suppressed(Rule::TagsMissingSection))
+        && !result.disable_check_tag
+    {
+        result.to_mut().disable_check_tag = true;
+    }
+    result
+}
+
+/// Confirms that the file written for `data` is still valid for zola and that
+/// nothing other than the front matter changed
+///
+/// Reads the file back, splits it and parses the front matter again, checks
+/// the content is unchanged and that running the fixes again would not change
+/// anything. Returns the reason if any of these fail.
+/// Writes the changes to the page and checks the file that was written
+/// (Restoring the original if the check fails). `applied` are the fixes that
+/// changed the page.
+fn write_page(
+    data: &FileData,
+    section_info: Option<&SectionInfo>,
+    options: &Options,
+    applied: &RuleStats,
+) -> Result<()> {
+    if options.strict_write {
+        data.verify_only_front_matter_changed()?;
+    }
+    data.write(options.backup)?;
+    if let Err(reason) = verify_write(data, section_info, options, applied) {
+        data.restore()?;
+        return Err(Error::WriteVerification {
+            path: data.path().to_path_buf(),
+            reason,
+        });
+    }
+    Ok(())
+}
+
+/// Reads the page back and checks that running the fixes in `applied` again
+/// would not change it
+fn verify_write(
+    data: &FileData,
+    section_info: Option<&SectionInfo>,
+    options: &Options,
+    applied: &RuleStats,
+) -> std::result::Result<(), &'static str> {
+    let path = data.path();
+    let text = fs::read_to_string(path).map_err(|_| "failed to read file back")?;
+    let mut written = FileData::new_from_str(path, &text).map_err(|_| "front matter not found")?;
+    written
+        .front_matter_as_toml()
+        .map_err(|_| "front matter is not valid TOML")?;
+    if written.content() != data.content() {
+        return Err("content of the file changed");
+    }
+    let section_info = load_page_settings(&written, section_info, options)
+        .map_err(|_| "failed to load settings")?;
+    let mut fixes = written
+        .update_series_and_tags(section_info.as_deref())
+        .map_err(|_| "failed to update tags and/or series")?;
+    if applied.updated_set > 0 {
+        fixes += written
+            .update_updated()
+            .map_err(|_| "failed to update updated")?;
+    }
+    if *fixes.stats().rules() != RuleStats::default() || written.is_changed() {
+        return Err("running the fixes again would change the file");
+    }
+    Ok(())
+}
+
+/// Processes a single page provided as text instead of reading it from disk
+///
+/// `path` is where the page would be located and is only used to find the
+/// section it belongs to. Returns the resulting text of the page, which is the
+/// input unchanged if no fixes were needed.
+pub fn filter_page(
+    content_root: &Path,
+    path: &Path,
+    text: &str,
+    options: &Options,
+) -> Result<(String, Report)> {
+    let mut result = Report::new();
+    if path.ends_with("_index.md") {
+        let (_, stats) = FileData::new_from_str(path, text)?.extract_section_info(options)?;
+        result += stats;
+        result.stats_mut().inc_not_changed();
+        return Ok((text.to_string(), result));
+    }
+    if should_skip_file(path) {
+        result.stats_mut().inc_skipped();
+        trace!("(Skipped)     {path:?}");
+        return Ok((text.to_string(), result));
+    }
+    let section_info = find_section_info(content_root, path, options)?;
+    let mut data = FileData::new_from_str(path, text)?;
+    let toml_doc = data.front_matter_as_toml()?;
+    let options = &*options.for_page(&toml_doc);
+    if is_draft(&toml_doc) && options.get_drafts() == DraftPolicy::Skip {
+        result.stats_mut().inc_skipped();
+        trace!("(Draft)       {path:?}");
+        return Ok((text.to_string(), result));
+    }
+    result += check_and_update(&mut data, options, section_info.as_ref())?;
+    if data.is_changed() {
+        result.add_changed(path);
+        if options.strict_write {
+            data.verify_only_front_matter_changed()?;
+        }
+        Ok((data.to_file_string(), result))
+    } else {
+        result.stats_mut().inc_not_changed();
+        Ok((text.to_string(), result))
+    }
+}
+
+/// Finds the section a page belongs to by looking for the closest `_index.md`
+/// in the folders from the page up to `content_root`
+pub(crate) fn find_section_info(
+    content_root: &Path,
+    page_path: &Path,
+    options: &Options,
+) -> Result<Option<SectionInfo>> {
+    for dir in page_path.ancestors().skip(1) {
+        if !dir.starts_with(content_root) {
+            break;
+        }
+        let section_path = dir.join("_index.md");
+        if section_path.is_file() {
+            let (section_info, _) =
+                FileData::new_from_path(&section_path)?.extract_section_info(options)?;
+            return Ok(Some(section_info));
+        }
+    }
+    Ok(None)
+}
+
 fn should_skip_file(path: &Path) -> bool {
     path.extension().is_none_or(|ext| ext != "md") || path.ends_with("_index.md")
 }
@@ -170,3 +700,137 @@ fn should_skip_file(path: &Path) -> bool {
 fn is_description_length_in_preferred_range(desc: &str) -> bool {
     PREFERRED_RANGE.contains(&desc.len())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+
+    #[test]
+    fn page_suppressions_without_section() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("post.md");
+        fs::write(
+            &path,
+            "+++\n# abridge-helper: ignore description-missing reason=\"landing page\"\n# abridge-helper: ignore invalid-date reason=\"imported\"\ntitle = \"Post\"\ndate = \"someday\"\n+++\nBody\n",
+        )
+        .unwrap();
+        let options = Options::new(dir.path()).check_only(true);
+        let report = process_file(&path, &options, None).unwrap();
+        assert_eq!(report.diagnostics(), []);
+        assert_eq!(report.suppressions().len(), 2);
+    }
+
+    #[test]
+    fn fixes_set_to_error_in_check_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("post.md");
+        fs::write(
+            &path,
+            "+++\ntitle = \"Post\"\ndescription = \"\"\n+++\nBody\n",
+        )
+        .unwrap();
+        let section_info = SectionInfo::new(None, "rust".to_string());
+        let options = Options::new(dir.path())
+            .check_only(true)
+            .ignore_seo(true)
+            .severity(Rule::SeriesNotSet, Severity::Error);
+        let report = process_file(&path, &options, Some(&section_info)).unwrap();
+        let found: Vec<_> = report
+            .diagnostics()
+            .iter()
+            .filter(|x| x.severity() == Severity::Error)
+            .map(|x| x.rule())
+            .collect();
+        assert_eq!(found, [Rule::SeriesNotSet]);
+        assert_eq!(report.stats().errors(), 1);
+        assert_eq!(report.pending_change_severity(), Some(Severity::Warning));
+        assert_eq!(report.exit_code(), 1);
+    }
+
+    #[test]
+    fn failed_write_keeps_the_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("post.md");
+        let original = "+++\ntitle = \"Post\"\n+++\nBody\n";
+        fs::write(&path, original).unwrap();
+        // The backup cannot be written over a folder
+        fs::create_dir(dir.path().join("post.md.orig")).unwrap();
+        let section_info = SectionInfo::new(None, "rust".to_string());
+        let options = Options::new(dir.path()).allow_dirty(true).backup(true);
+        let report = process_file(&path, &options, Some(&section_info)).unwrap();
+        assert!(matches!(report.failures(), [Error::Io { .. }]));
+        assert_eq!(report.diagnostics()[0].rule(), Rule::DescriptionMissing);
+        assert_eq!(report.stats().rules().series_set, 1);
+        assert!(report.changed_files().is_empty());
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+    }
+
+    #[test]
+    fn updated_is_verified() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("post.md");
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(dir.path())
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .env("GIT_AUTHOR_DATE", "2024-02-01T10:00:00Z")
+                .env("GIT_COMMITTER_DATE", "2024-02-01T10:00:00Z")
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        git(&["init", "-q"]);
+        for body in ["Body", "New body"] {
+            let text = format!("+++\ntitle = \"Post\"\ndate = 2024-01-01\n+++\n{body}\n");
+            fs::write(&path, text).unwrap();
+            git(&["add", "-A"]);
+            git(&["commit", "-qm", "edit"]);
+        }
+        let original = fs::read_to_string(&path).unwrap();
+        let mut data = FileData::new_from_str(&path, &original).unwrap();
+        let report = data.update_updated().unwrap();
+        assert_eq!(report.stats().rules().updated_set, 1);
+        let options = Options::new(dir.path()).set_updated(true);
+
+        data.write(false).unwrap();
+        let applied = *report.stats().rules();
+        assert_eq!(verify_write(&data, None, &options, &applied), Ok(()));
+        // A write that lost the change to `updated` is caught
+        fs::write(&path, &original).unwrap();
+        assert_eq!(
+            verify_write(&data, None, &options, &applied),
+            Err("running the fixes again would change the file")
+        );
+    }
+
+    #[test]
+    fn backup_and_restore_on_failed_verification() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("post.md");
+        let original = "+++\ntitle = \"Post\"\n+++\nBody\n";
+        fs::write(&path, original).unwrap();
+        let section_info = SectionInfo::new(None, "rust".to_string());
+        let options = Options::new(dir.path()).allow_dirty(true).backup(true);
+        let report = process_file(&path, &options, Some(&section_info)).unwrap();
+        assert_eq!(report.stats().rules().series_set, 1);
+        assert_eq!(
+            fs::read_to_string(path.with_extension("md.orig")).unwrap(),
+            original
+        );
+
+        // A write that does not read back as expected is undone
+        let text = fs::read_to_string(&path).unwrap();
+        let data = FileData::new_from_str(&path, &text).unwrap();
+        fs::write(&path, text.replace("Body", "Other")).unwrap();
+        assert_eq!(
+            verify_write(&data, Some(&section_info), &options, &RuleStats::default()),
+            Err("content of the file changed")
+        );
+        data.restore().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), text);
+    }
+}
@@ -1,66 +1,168 @@
-use std::{fs, io::Write, path::Path};
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::Write,
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{Context, bail};
 use once_cell::sync::Lazy;
 use regex::Regex;
+use tempfile::NamedTempFile;
 use toml_edit::DocumentMut;
 use tracing::error;
 
-use crate::{Cli, Stats, TOML_KEY_EXTRA, section_info::SectionInfo};
+use crate::{
+    Options, Report, TOML_KEY_EXTRA,
+    dates::updated_from_history,
+    error::{Error, Result},
+    history::last_body_change,
+    rule::{Rule, Severity},
+    section_info::{SectionInfo, SortBy},
+    site::SiteFile,
+    suppression::parse_suppressions,
+};
 
-use super::check_description;
+use super::{check_description, check_settings, is_draft, is_transparent_set, log_diagnostic};
 
 static TOML_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(
-        r"^[[:space:]]*\+\+\+(\r?\n(?s).*?(?-s))\+\+\+[[:space:]]*(?:$|(?:\r?\n((?s).*(?-s))$))",
+        r"^\x{FEFF}?[[:space:]]*\+\+\+(\r?\n(?s).*?(?-s))\+\+\+[[:space:]]*(?:$|(?:\r?\n((?s).*(?-s))$))",
     )
     .unwrap()
 });
 
+/// Byte ranges of the parts of the text of a file
+///
+/// Everything before `front_matter` (BOM, leading whitespace and the opening
+/// `+++`) and between `front_matter` and `content` (closing `+++` and the
+/// separator) is kept as is when writing
+struct Parts {
+    front_matter: Range<usize>,
+    content: Range<usize>,
+}
+
+impl Parts {
+    /// Patterned on zola code https://github.com/c-git/zola/blob/3a73c9c5449f2deda0d287f9359927b0440a77af/components/content/src/front_matter/split.rs#L46
+    fn new(text: &str) -> Option<Self> {
+        let caps = TOML_RE.captures(text)?;
+        // caps[0] is the full match
+        // caps[1] => front matter
+        // caps[2] => content
+        let front_matter = caps.get(1)?.range();
+        let content = caps.get(2).map_or(text.len()..text.len(), |m| m.range());
+        Some(Self {
+            front_matter,
+            content,
+        })
+    }
+}
+
+/// A zola content file split into front matter and content
+///
+/// Only the front matter can be changed. Everything else (BOM, leading
+/// whitespace, separator and content) is written back exactly as it was read.
+#[derive(Debug)]
 pub struct FileData<'a> {
     is_changed: bool,
     path: &'a Path,
+    /// BOM (if any), leading whitespace and the opening `+++`
+    leading: String,
     front_matter: String,
+    /// Closing `+++` and whatever separates it from the content
+    separator: String,
     content: String,
+    /// Line ending used in the front matter when it was read
+    line_ending: &'static str,
+    /// Text of the file as it was read
+    original: String,
 }
 
 impl<'a> FileData<'a> {
     /// Write changes to disk.
     ///
+    /// The new text is written to a temporary file in the same folder which is
+    /// then renamed over the original so the file is never left partially
+    /// written. If `keep_backup` is set the text that was read is saved next to
+    /// the file with `.orig` appended to the file name.
+    ///
     /// Precondition: Data is changed. If not changed function returns an error
     /// to avoid writing out the same data read in.
-    pub fn write(&self) -> anyhow::Result<()> {
+    pub fn write(&self, keep_backup: bool) -> Result<()> {
         if !self.is_changed() {
-            bail!("No change detected. Write aborted. Path: {:?}", self.path);
+            return Err(Error::NotChanged {
+                path: self.path.to_path_buf(),
+            });
         }
-        let mut file = fs::OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(self.path)?;
-        let mut s = "+++".to_string();
-        s.push_str(&self.front_matter);
-        s.push_str("+++\n");
-        if !self.content.is_empty() {
-            // Added a space between to match `dprint`
-            s.push('\n');
+        let permissions = fs::metadata(self.path)
+            .map_err(|e| Error::io(self.path, "Failed to read metadata of file", e))?
+            .permissions();
+        if keep_backup {
+            let backup_path = backup_path(self.path);
+            fs::write(&backup_path, &self.original)
+                .map_err(|e| Error::io(&backup_path, "Failed to write backup", e))?;
         }
+        write_atomically(self.path, &self.to_file_string(), permissions)
+    }
+
+    /// Writes the text of the file as it was read back to disk (Used to undo a
+    /// write)
+    pub fn restore(&self) -> Result<()> {
+        let permissions = fs::metadata(self.path)
+            .map_err(|e| Error::io(self.path, "Failed to read metadata of file", e))?
+            .permissions();
+        write_atomically(self.path, &self.original, permissions)
+    }
+
+    /// Builds the full text of the file (front matter and content) as it
+    /// would be written to disk
+    ///
+    /// Only the front matter can differ from the text that was read
+    pub fn to_file_string(&self) -> String {
+        let mut s = String::with_capacity(self.original.len() + self.front_matter.len());
+        s.push_str(&self.leading);
+        s.push_str(&self.front_matter);
+        s.push_str(&self.separator);
         s.push_str(&self.content);
-        file.write_all(s.as_bytes())?;
-        Ok(())
+        s
     }
 
-    pub(crate) fn front_matter_as_toml(&self) -> anyhow::Result<DocumentMut> {
+    /// Confirms that writing would not change any bytes outside of the front
+    /// matter (Used for strict writes)
+    pub fn verify_only_front_matter_changed(&self) -> Result<()> {
+        let new_text = self.to_file_string();
+        let is_same_outside_front_matter = match (Parts::new(&self.original), Parts::new(&new_text))
+        {
+            (Some(original), Some(new)) => {
+                self.original[..original.front_matter.start] == new_text[..new.front_matter.start]
+                    && self.original[original.front_matter.end..]
+                        == new_text[new.front_matter.end..]
+            }
+            _ => false,
+        };
+        if is_same_outside_front_matter {
+            Ok(())
+        } else {
+            Err(Error::StrictWriteRefused {
+                path: self.path.to_path_buf(),
+            })
+        }
+    }
+
+    /// Parses the front matter
+    pub fn front_matter_as_toml(&self) -> Result<DocumentMut> {
         let toml = &self.front_matter[..];
         let result = toml
             .parse::<DocumentMut>()
-            .context("Failed to parse TOML in front matter")?;
-        debug_assert_eq!(result.to_string(), toml);
+            .map_err(|e| Error::invalid_toml(self.path, e))?;
+        debug_assert_eq!(result.to_string(), toml.replace("\r\n", "\n"));
         Ok(result)
     }
 
-    /// Extract info about a section (Name and Stats)
-    /// Checks the transparent is set and is a boolean
-    pub fn extract_section_info(&self) -> anyhow::Result<(SectionInfo, Stats)> {
+    /// Extract info about a section (Name and Report)
+    /// Checks the transparent is set and is a boolean (Reported as a
+    /// diagnostic at the severity of the rule)
+    pub(crate) fn extract_section_info(&self, options: &Options) -> Result<(SectionInfo, Report)> {
         let doc = self.front_matter_as_toml()?;
 
         let Some(section_folder) = self
@@ -68,10 +170,9 @@ impl<'a> FileData<'a> {
             .parent()
             .and_then(|x| x.file_name().map(|x| x.to_string_lossy().to_string()))
         else {
-            bail!(
-                "failed to get section folder name for file at: {:?}",
-                self.path
-            );
+            return Err(Error::SectionFolderName {
+                path: self.path.to_path_buf(),
+            });
         };
 
         let section_title = doc
@@ -79,128 +180,389 @@ impl<'a> FileData<'a> {
             .and_then(|x| x.as_str().map(|s| s.to_string()));
 
         let mut result_section_info = SectionInfo::new(section_title, section_folder);
-        let mut result_stats = Stats::new();
+        let mut result_report = Report::new();
 
         result_section_info = result_section_info.load_settings(&doc).into_owned();
+        result_section_info.sort_by = SortBy::from_section(&doc);
+        result_section_info.path = Some(self.path.to_path_buf());
 
-        if !doc.get("transparent").is_some_and(|x| x.is_bool()) {
-            error!(
-                "Transparent not set or not bool for section in file at: {:?}",
-                self.path
-            );
-            result_stats.inc_errors();
+        let (suppressions, invalid) = parse_suppressions(self.path, &self.front_matter);
+        for e in invalid {
+            error!("{e}");
+            result_report.add_failure(e);
         }
-        Ok((result_section_info, result_stats))
-    }
+        result_section_info
+            .suppressed
+            .extend(suppressions.iter().map(|x| x.rule()));
+        result_report.add_suppressions(suppressions);
 
-    fn new(path: &'a Path, front_matter: String, content: String) -> Self {
-        Self {
-            is_changed: false,
-            path,
-            front_matter,
-            content,
+        result_report += check_settings(&doc, options, self.path, &result_section_info.suppressed);
+        result_report.add_site_file(SiteFile {
+            path: self.path.to_path_buf(),
+            front_matter: doc.clone(),
+            content: self.content.clone(),
+            content_start_line: self.content_start_line(),
+            suppressed: result_section_info.suppressed.clone(),
+            section: None,
+            is_draft: false,
+        });
+
+        if !is_transparent_set(&doc) && !result_section_info.is_suppressed(Rule::TransparentNotSet)
+        {
+            let e = Error::TransparentNotSet {
+                path: self.path.to_path_buf(),
+            };
+            let severity = options.rule_severity(Rule::TransparentNotSet);
+            if severity != Severity::Off {
+                let checked_value = doc
+                    .get("transparent")
+                    .map(|x| x.to_string())
+                    .unwrap_or_default();
+                log_diagnostic(severity, Rule::TransparentNotSet, &e.to_string());
+                result_report.add_diagnostic(
+                    self.path,
+                    Rule::TransparentNotSet,
+                    severity,
+                    e.to_string(),
+                    &checked_value,
+                );
+            }
         }
+        Ok((result_section_info, result_report))
     }
 
-    pub(crate) fn is_changed(&self) -> bool {
+    /// True if the front matter has been changed since it was read
+    pub fn is_changed(&self) -> bool {
         self.is_changed
     }
 
+    /// Path of the file
+    pub fn path(&self) -> &'a Path {
+        self.path
+    }
+
+    /// Text of the front matter (between the `+++` lines)
+    pub fn front_matter(&self) -> &str {
+        &self.front_matter
+    }
+
+    /// Text after the front matter
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// Line number (starting at 1) in the file as it was read of the first
+    /// line of the content
+    pub fn content_start_line(&self) -> usize {
+        let before_content = &self.original[..self.original.len() - self.content.len()];
+        before_content.matches('\n').count() + 1
+    }
+
     /// Build a FileData from a path
     ///
     /// Splits the file data into front matter and content
-    /// Patterned on zola code https://github.com/c-git/zola/blob/3a73c9c5449f2deda0d287f9359927b0440a77af/components/content/src/front_matter/split.rs#L46
-    pub fn new_from_path(path: &Path) -> anyhow::Result<FileData> {
-        let content = fs::read_to_string(path).context("Failed to read file")?;
+    pub fn new_from_path(path: &Path) -> Result<FileData<'_>> {
+        let content =
+            fs::read_to_string(path).map_err(|e| Error::io(path, "Failed to read file", e))?;
+        FileData::new_from_str(path, &content)
+    }
 
-        // 2. extract the front matter and the content
-        let caps = if let Some(caps) = TOML_RE.captures(&content) {
-            caps
+    /// Build a FileData from text that has already been read
+    ///
+    /// `path` is only used to identify the file (for section names and
+    /// messages) and is not read from or written to.
+    pub fn new_from_str(path: &'a Path, text: &str) -> Result<Self> {
+        let Some(parts) = Parts::new(text) else {
+            return Err(Error::FrontMatterNotFound {
+                path: path.to_path_buf(),
+            });
+        };
+        let front_matter = &text[parts.front_matter.clone()];
+        let line_ending = if front_matter.starts_with("\r\n") {
+            "\r\n"
         } else {
-            bail!("Failed to find front matter");
+            "\n"
         };
-        // caps[0] is the full match
-        // caps[1] => front matter
-        // caps[2] => content
-        let front_matter = caps.get(1).unwrap().as_str().to_string();
-        let content = caps.get(2).map_or("", |m| m.as_str()).to_string();
-
-        Ok(FileData::new(path, front_matter, content))
+        Ok(Self {
+            is_changed: false,
+            path,
+            leading: text[..parts.front_matter.start].to_string(),
+            front_matter: front_matter.to_string(),
+            separator: text[parts.front_matter.end..parts.content.start].to_string(),
+            content: text[parts.content].to_string(),
+            line_ending,
+            original: text.to_string(),
+        })
     }
 
     pub(crate) fn check_description(
         &self,
-        cli: &Cli,
+        options: &Options,
         section_info: Option<&SectionInfo>,
-    ) -> anyhow::Result<Stats> {
-        if let Some(section_info) = section_info {
-            if section_info.disable_check_description {
-                return Ok(Stats::default());
-            }
+        suppressed: &BTreeSet<Rule>,
+    ) -> Result<Report> {
+        if let Some(section_info) = section_info
+            && section_info.disable_check_description
+        {
+            return Ok(Report::new());
         }
         let toml_doc = self.front_matter_as_toml()?;
-        Ok(check_description(&toml_doc, cli, self.path))
+        Ok(check_description(&toml_doc, options, self.path, suppressed))
     }
 
+    /// Sets the series and adds the tag for the section the page is in (Crate
+    /// only as [`SectionInfo`] is not part of the public API)
     pub(crate) fn update_series_and_tags(
         &mut self,
         section_info: Option<&SectionInfo>,
-    ) -> anyhow::Result<()> {
+    ) -> Result<Report> {
+        let mut result = Report::new();
         let Some(section_info) = section_info else {
-            return Ok(());
+            return Ok(result);
         };
         let mut doc = self.front_matter_as_toml()?;
 
-        // Set series
-        if !section_info.disable_check_series {
-            let series_name = section_info.section_name();
-            let key_series = "series";
-
-            // Check if no change is needed
-            let mut is_change_needed = true;
-            if let Some(extra) = doc.get(TOML_KEY_EXTRA) {
-                if let Some(series) = extra.get(key_series) {
-                    if Some(series_name) == series.as_str() {
-                        // Already equal no need to make any change
-                        is_change_needed = false;
-                    }
-                }
-            }
-            if is_change_needed {
-                self.is_changed = true;
-                doc[TOML_KEY_EXTRA][key_series] = series_name.into();
-            }
+        let is_series_changed = set_series(&mut doc, section_info);
+        let is_tags_changed = set_tags(&mut doc, section_info);
+
+        // Save changes if any
+        if is_series_changed || is_tags_changed {
+            self.set_front_matter(&doc);
         }
+        let rules = result.stats_mut().rules_mut();
+        rules.series_set = is_series_changed.into();
+        rules.tags_added = is_tags_changed.into();
+        Ok(result)
+    }
 
-        // Set tags
-        let key_taxonomies = "taxonomies";
-        let key_tags = "tags";
-        if !section_info.disable_check_tag {
-            let tag_name = section_info.section_name();
-            let mut force_set_tag = |doc: &mut DocumentMut| {
-                self.is_changed = true;
-                let mut array = toml_edit::Array::new();
-                array.push(tag_name);
-                doc[key_taxonomies][key_tags] = array.into();
-            };
-            if let Some(taxonomies) = doc.get_mut(key_taxonomies) {
-                if let Some(tags) = taxonomies.get_mut(key_tags).and_then(|x| x.as_array_mut()) {
-                    if !tags.iter().any(|x| x.as_str() == Some(tag_name)) {
-                        self.is_changed = true;
-                        tags.push(tag_name);
-                    }
-                } else {
-                    force_set_tag(&mut doc);
-                }
-            } else {
-                force_set_tag(&mut doc);
-            }
+    /// Sets `updated` to when the body of the page last changed according to
+    /// the git history (See [`updated_from_history`])
+    pub(crate) fn update_updated(&mut self) -> Result<Report> {
+        let mut result = Report::new();
+        let Some(last_change) = last_body_change(self.path)? else {
+            return Ok(result);
+        };
+        let mut doc = self.front_matter_as_toml()?;
+        if let Some(updated) = updated_from_history(&doc, last_change) {
+            doc["updated"] = toml_edit::value(updated);
+            self.set_front_matter(&doc);
+            let rules = result.stats_mut().rules_mut();
+            rules.updated_set = rules.updated_set.saturating_add(1);
         }
+        Ok(result)
+    }
 
-        // Save changes if any
-        if self.is_changed {
-            self.front_matter = doc.to_string();
+    /// What is kept about the page for the site wide checks
+    pub(crate) fn to_site_file(
+        &self,
+        section_info: Option<&SectionInfo>,
+        suppressed: BTreeSet<Rule>,
+    ) -> Result<SiteFile> {
+        let front_matter = self.front_matter_as_toml()?;
+        Ok(SiteFile {
+            path: self.path.to_path_buf(),
+            is_draft: is_draft(&front_matter),
+            front_matter,
+            content: self.content.clone(),
+            content_start_line: self.content_start_line(),
+            suppressed,
+            section: section_info.and_then(|info| info.path.clone()),
+        })
+    }
+
+    /// Replaces the front matter with `doc`
+    ///
+    /// The line ending used when the file was read is kept. The file is only
+    /// marked as changed if the resulting front matter is different.
+    pub fn set_front_matter(&mut self, doc: &DocumentMut) {
+        let front_matter = match self.line_ending {
+            "\n" => doc.to_string(),
+            line_ending => doc
+                .to_string()
+                .replace("\r\n", "\n")
+                .replace('\n', line_ending),
+        };
+        if front_matter != self.front_matter {
+            self.front_matter = front_matter;
+            self.is_changed = true;
         }
-        Ok(())
+    }
+}
+
+/// Writes `text` to a temporary file in the same folder as `path` then renames
+/// it to `path`
+fn write_atomically(path: &Path, text: &str, permissions: fs::Permissions) -> Result<()> {
+    // Resolve symlinks so the target is replaced instead of the link itself
+    let path = &fs::canonicalize(path).map_err(|e| Error::io(path, "Failed to resolve path", e))?;
+    let folder = match path.parent() {
+        Some(folder) if !folder.as_os_str().is_empty() => folder,
+        _ => Path::new("."),
+    };
+    let mut temp_file = NamedTempFile::new_in(folder)
+        .map_err(|e| Error::io(folder, "Failed to create temporary file", e))?;
+    let temp_path = temp_file.path().to_path_buf();
+    temp_file
+        .write_all(text.as_bytes())
+        .map_err(|e| Error::io(&temp_path, "Failed to write to temporary file", e))?;
+    temp_file
+        .as_file()
+        .set_permissions(permissions)
+        .map_err(|e| Error::io(&temp_path, "Failed to set permissions on temporary file", e))?;
+    temp_file
+        .as_file()
+        .sync_all()
+        .map_err(|e| Error::io(&temp_path, "Failed to sync temporary file", e))?;
+    temp_file
+        .persist(path)
+        .map_err(|e| Error::io(path, "Failed to replace file with temporary file", e.error))?;
+    sync_folder(folder)
+}
+
+/// Flushes the folder entry so a rename into it survives a crash (Not supported
+/// on Windows where renames are already durable once they return)
+fn sync_folder(folder: &Path) -> Result<()> {
+    if cfg!(windows) {
+        return Ok(());
+    }
+    fs::File::open(folder)
+        .and_then(|dir| dir.sync_all())
+        .map_err(|e| Error::io(folder, "Failed to sync folder", e))
+}
+
+/// Path used to keep a copy of the original file (`.orig` appended to the file
+/// name)
+fn backup_path(path: &Path) -> PathBuf {
+    let mut result = path.as_os_str().to_owned();
+    result.push(".orig");
+    result.into()
+}
+
+/// Returns the byte range of the front matter (excluding the `+++` lines) in
+/// the text of a file if front matter is found
+pub(crate) fn front_matter_span(content: &str) -> Option<Range<usize>> {
+    Parts::new(content).map(|parts| parts.front_matter)
+}
+
+/// Ensures the series is set to the section name unless disabled. Returns true
+/// if `doc` was changed
+pub(crate) fn set_series(doc: &mut DocumentMut, section_info: &SectionInfo) -> bool {
+    if section_info.disable_check_series {
+        return false;
+    }
+    let series_name = section_info.section_name();
+    let key_series = "series";
+
+    // Check if no change is needed
+    if let Some(extra) = doc.get(TOML_KEY_EXTRA)
+        && let Some(series) = extra.get(key_series)
+        && Some(series_name) == series.as_str()
+    {
+        // Already equal no need to make any change
+        return false;
+    }
+    doc[TOML_KEY_EXTRA][key_series] = series_name.into();
+    true
+}
+
+/// Ensures the tags include the section name unless disabled. Returns true if
+/// `doc` was changed
+pub(crate) fn set_tags(doc: &mut DocumentMut, section_info: &SectionInfo) -> bool {
+    let key_taxonomies = "taxonomies";
+    let key_tags = "tags";
+    if section_info.disable_check_tag {
+        return false;
+    }
+    let tag_name = section_info.section_name();
+    if let Some(taxonomies) = doc.get_mut(key_taxonomies)
+        && let Some(tags) = taxonomies.get_mut(key_tags).and_then(|x| x.as_array_mut())
+    {
+        if tags.iter().any(|x| x.as_str() == Some(tag_name)) {
+            return false;
+        }
+        tags.push(tag_name);
+    } else {
+        let mut array = toml_edit::Array::new();
+        array.push(tag_name);
+        doc[key_taxonomies][key_tags] = array.into();
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn only_front_matter_changes_on_write() {
+        let original = "\u{feff}\n+++\r\ntitle = \"Post\"\r\n+++\r\n\r\n\r\n# Heading\r\n";
+        let section_info = SectionInfo::new(None, "rust".to_string());
+        let mut data = FileData::new_from_str(Path::new("rust/post.md"), original).unwrap();
+        let report = data.update_series_and_tags(Some(&section_info)).unwrap();
+        assert_eq!(report.stats().rules().series_set, 1);
+        assert_eq!(report.stats().rules().tags_added, 1);
+        assert!(data.is_changed());
+        data.verify_only_front_matter_changed().unwrap();
+        let expected = "\u{feff}\n+++\r\ntitle = \"Post\"\r\nextra = { series = \"rust\" }\r\ntaxonomies = { tags = [\"rust\"] }\r\n+++\r\n\r\n\r\n# Heading\r\n";
+        assert_eq!(data.to_file_string(), expected);
+    }
+
+    #[test]
+    fn write_requires_a_change() {
+        let data = FileData::new_from_str(Path::new("post.md"), "+++\n+++\n").unwrap();
+        assert!(matches!(data.write(false), Err(Error::NotChanged { .. })));
+    }
+
+    #[test]
+    fn body_changes_are_refused() {
+        let section_info = SectionInfo::new(None, "rust".to_string());
+        let mut data =
+            FileData::new_from_str(Path::new("rust/post.md"), "+++\n+++\nBody\n").unwrap();
+        let report = data.update_series_and_tags(Some(&section_info)).unwrap();
+        assert_eq!(report.stats().rules().series_set, 1);
+        data.content.push_str("More\n");
+        assert!(matches!(
+            data.verify_only_front_matter_changed(),
+            Err(Error::StrictWriteRefused { .. })
+        ));
+    }
+
+    #[test]
+    fn crlf_write_backup_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("post.md");
+        let original = "+++\r\ntitle = \"Post\"\r\n+++\r\nBody\r\n";
+        fs::write(&path, original).unwrap();
+        let section_info = SectionInfo::new(None, "rust".to_string());
+        let mut data = FileData::new_from_path(&path).unwrap();
+        let report = data.update_series_and_tags(Some(&section_info)).unwrap();
+        assert_eq!(report.stats().rules().tags_added, 1);
+        data.write(true).unwrap();
+
+        let expected = "+++\r\ntitle = \"Post\"\r\nextra = { series = \"rust\" }\r\ntaxonomies = { tags = [\"rust\"] }\r\n+++\r\nBody\r\n";
+        assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+        assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), original);
+        data.restore().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_keeps_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("target.md");
+        let link = dir.path().join("link.md");
+        fs::write(&target, "+++\ntitle = \"Post\"\n+++\nBody\n").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        write_atomically(&link, "new", fs::metadata(&link).unwrap().permissions()).unwrap();
+        assert!(
+            fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
     }
 }
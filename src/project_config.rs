@@ -0,0 +1,243 @@
+//! Settings for the tool that are stored with the site
+
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use clap::ValueEnum as _;
+
+use toml_edit::DocumentMut;
+
+use crate::{
+    DraftPolicy, SymlinkPolicy,
+    error::{Error, Result},
+    rule::{Rule, Severity},
+};
+
+/// Name of the file (in the root of the site) with the project config
+pub const PROJECT_CONFIG_FILE: &str = "zola_abridge_helper.toml";
+
+/// Settings loaded from [`PROJECT_CONFIG_FILE`]
+///
+/// ```toml
+/// [rules]
+/// description-length = "info"
+/// series-not-set = "off"
+///
+/// [duplicates]
+/// threshold = 90 # Percent similarity at which values are reported
+///
+/// [images]
+/// max_size_kb = 500 # Size above which referenced images are reported
+///
+/// [files]
+/// include = ["blog/**"] # Globs relative to the content folder
+/// exclude = ["blog/drafts/**"]
+/// gitignore = true # Respect `.gitignore`
+/// hidden = false # Process hidden files and folders
+/// symlinks = "follow" # Or "skip" or "error"
+///
+/// [drafts]
+/// policy = "check" # Or "skip", "no-fail" or "fix-only"
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct ProjectConfig {
+    pub(crate) severities: BTreeMap<Rule, Severity>,
+    pub(crate) duplicate_threshold: Option<u8>,
+    pub(crate) max_image_size_kb: Option<u64>,
+    pub(crate) include_globs: Vec<String>,
+    pub(crate) exclude_globs: Vec<String>,
+    pub(crate) no_gitignore: bool,
+    pub(crate) hidden: bool,
+    pub(crate) symlinks: Option<SymlinkPolicy>,
+    pub(crate) drafts: Option<DraftPolicy>,
+}
+
+impl ProjectConfig {
+    /// Loads the config for the site at `root_path` (Defaults are used if there
+    /// is no config file)
+    pub(crate) fn load(root_path: &Path) -> Result<Self> {
+        let path = root_path.join(PROJECT_CONFIG_FILE);
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(source) => return Err(Error::ConfigUnreadable { path, source }),
+        };
+        Self::parse(&path, &text)
+    }
+
+    fn parse(path: &Path, text: &str) -> Result<Self> {
+        let invalid = |message: String| Error::InvalidProjectConfig {
+            path: path.to_path_buf(),
+            message,
+        };
+        let doc = text
+            .parse::<DocumentMut>()
+            .map_err(|e| Error::invalid_toml(path, e))?;
+        let mut result = Self::default();
+        for (key, _) in doc.iter() {
+            if !["rules", "duplicates", "images", "files", "drafts"].contains(&key) {
+                return Err(invalid(format!("unknown key {key:?}")));
+            }
+        }
+        let table = |key: &str| match doc.get(key) {
+            None => Ok(None),
+            Some(item) => item
+                .as_table_like()
+                .map(Some)
+                .ok_or_else(|| invalid(format!("`{key}` must be a table"))),
+        };
+        if let Some(duplicates) = table("duplicates")? {
+            for (key, value) in duplicates.iter() {
+                if key != "threshold" {
+                    return Err(invalid(format!("unknown key {key:?} in `duplicates`")));
+                }
+                let threshold = value
+                    .as_integer()
+                    .and_then(|x| u8::try_from(x).ok())
+                    .filter(|x| (1..=100).contains(x))
+                    .ok_or_else(|| {
+                        invalid("`duplicates.threshold` must be a percentage (1-100)".to_string())
+                    })?;
+                result.duplicate_threshold = Some(threshold);
+            }
+        }
+        if let Some(images) = table("images")? {
+            for (key, value) in images.iter() {
+                if key != "max_size_kb" {
+                    return Err(invalid(format!("unknown key {key:?} in `images`")));
+                }
+                let max_size = value
+                    .as_integer()
+                    .and_then(|x| u64::try_from(x).ok())
+                    .filter(|x| *x > 0)
+                    .ok_or_else(|| {
+                        invalid("`images.max_size_kb` must be a positive integer".to_string())
+                    })?;
+                result.max_image_size_kb = Some(max_size);
+            }
+        }
+        if let Some(files) = table("files")? {
+            for (key, value) in files.iter() {
+                match key {
+                    "include" | "exclude" => {
+                        let globs = value
+                            .as_array()
+                            .and_then(|x| {
+                                x.iter()
+                                    .map(|x| x.as_str().map(str::to_string))
+                                    .collect::<Option<Vec<_>>>()
+                            })
+                            .ok_or_else(|| {
+                                invalid(format!("`files.{key}` must be an array of strings"))
+                            })?;
+                        if key == "include" {
+                            result.include_globs = globs;
+                        } else {
+                            result.exclude_globs = globs;
+                        }
+                    }
+                    "gitignore" | "hidden" => {
+                        let value = value
+                            .as_bool()
+                            .ok_or_else(|| invalid(format!("`files.{key}` must be a boolean")))?;
+                        if key == "gitignore" {
+                            result.no_gitignore = !value;
+                        } else {
+                            result.hidden = value;
+                        }
+                    }
+                    "symlinks" => {
+                        let policy = value
+                            .as_str()
+                            .and_then(|x| SymlinkPolicy::from_str(x, false).ok())
+                            .ok_or_else(|| {
+                                invalid(
+                                    "`files.symlinks` must be one of: follow, skip, error"
+                                        .to_string(),
+                                )
+                            })?;
+                        result.symlinks = Some(policy);
+                    }
+                    _ => return Err(invalid(format!("unknown key {key:?} in `files`"))),
+                }
+            }
+        }
+        if let Some(drafts) = table("drafts")? {
+            for (key, value) in drafts.iter() {
+                if key != "policy" {
+                    return Err(invalid(format!("unknown key {key:?} in `drafts`")));
+                }
+                let policy = value
+                    .as_str()
+                    .and_then(|x| DraftPolicy::from_str(x, false).ok())
+                    .ok_or_else(|| {
+                        invalid(
+                            "`drafts.policy` must be one of: check, skip, no-fail, fix-only"
+                                .to_string(),
+                        )
+                    })?;
+                result.drafts = Some(policy);
+            }
+        }
+        let Some(rules) = table("rules")? else {
+            return Ok(result);
+        };
+        for (key, value) in rules.iter() {
+            let rule = key.parse::<Rule>().map_err(invalid)?;
+            let Some(severity) = value.as_str() else {
+                return Err(invalid(format!("severity for {key:?} must be a string")));
+            };
+            let severity = severity.parse::<Severity>().map_err(invalid)?;
+            result.severities.insert(rule, severity);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn parse_rules() {
+        let path = Path::new(PROJECT_CONFIG_FILE);
+        let config = ProjectConfig::parse(
+            path,
+            "[rules]\ndescription-length = \"info\"\nseries-not-set = \"off\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.severities,
+            BTreeMap::from([
+                (Rule::DescriptionLength, Severity::Info),
+                (Rule::SeriesNotSet, Severity::Off),
+            ])
+        );
+        assert!(ProjectConfig::parse(path, "[rules]\nnot-a-rule = \"info\"\n").is_err());
+        assert!(ProjectConfig::parse(path, "[rules]\ndescription-length = \"loud\"\n").is_err());
+    }
+
+    #[test]
+    fn parse_files() {
+        let path = Path::new(PROJECT_CONFIG_FILE);
+        let config = ProjectConfig::parse(
+            path,
+            "[files]\ninclude = [\"blog/**\"]\nexclude = [\"blog/drafts/**\"]\ngitignore = false\n",
+        )
+        .unwrap();
+        assert_eq!(config.include_globs, ["blog/**"]);
+        assert_eq!(config.exclude_globs, ["blog/drafts/**"]);
+        assert!(config.no_gitignore);
+        assert!(!config.hidden);
+        assert!(ProjectConfig::parse(path, "[files]\ninclude = \"blog/**\"\n").is_err());
+    }
+
+    #[test]
+    fn parse_drafts() {
+        let path = Path::new(PROJECT_CONFIG_FILE);
+        let config = ProjectConfig::parse(path, "[drafts]\npolicy = \"no-fail\"\n").unwrap();
+        assert_eq!(config.drafts, Some(DraftPolicy::NoFail));
+        assert!(ProjectConfig::parse(path, "[drafts]\npolicy = \"ignore\"\n").is_err());
+    }
+}
@@ -0,0 +1,316 @@
+//! Results of a run
+
+use std::{
+    ops::AddAssign,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    BaselineEntry, Error, Stats,
+    baseline::fingerprint,
+    rule::{Rule, Severity},
+    site::SiteFile,
+    suppression::Suppression,
+};
+
+/// Everything found during a run
+#[derive(Debug, Default)]
+#[must_use]
+pub struct Report {
+    stats: Stats,
+    failures: Vec<Error>,
+    diagnostics: Vec<Diagnostic>,
+    changed_files: Vec<PathBuf>,
+    pending_change_severity: Option<Severity>,
+    fixed_baseline_entries: Vec<BaselineEntry>,
+    suppressions: Vec<Suppression>,
+    site_files: Vec<SiteFile>,
+}
+
+/// A problem found in a specific file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    path: PathBuf,
+    rule: Rule,
+    severity: Severity,
+    message: String,
+    fingerprint: u64,
+    is_baselined: bool,
+}
+
+impl Diagnostic {
+    /// The file the problem was found in
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The rule that found the problem
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    /// Severity configured for the rule when the problem was found
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Description of the problem
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Hash of the value the rule checked (Stable across runs and builds)
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    /// True if the problem is recorded in the baseline (Only known problems
+    /// do not fail the run)
+    pub fn is_baselined(&self) -> bool {
+        self.is_baselined
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}] {}, Path: {:?}",
+            self.rule.id(),
+            self.message,
+            self.path
+        )
+    }
+}
+
+impl Report {
+    pub(crate) fn new() -> Self {
+        Self {
+            stats: Stats::new(),
+            ..Default::default()
+        }
+    }
+
+    /// Counts of what happened during the run
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    pub(crate) fn stats_mut(&mut self) -> &mut Stats {
+        &mut self.stats
+    }
+
+    /// Errors that occurred (Each includes the path it occurred on)
+    pub fn failures(&self) -> &[Error] {
+        &self.failures
+    }
+
+    /// Problems found by the rules (Includes those in the baseline)
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Problems found that are not in the baseline (All of them if no
+    /// baseline was used)
+    pub fn new_diagnostics(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|x| !x.is_baselined)
+    }
+
+    /// Entries in the baseline that no longer match a problem and can be
+    /// removed by updating the baseline
+    pub fn fixed_baseline_entries(&self) -> &[BaselineEntry] {
+        &self.fixed_baseline_entries
+    }
+
+    /// Rules turned off by comments in the front matter of the files
+    /// processed
+    pub fn suppressions(&self) -> &[Suppression] {
+        &self.suppressions
+    }
+
+    /// Files that were changed (or would have been changed in check only mode)
+    pub fn changed_files(&self) -> &[PathBuf] {
+        &self.changed_files
+    }
+
+    /// Highest severity of the rules with changes that were not made because
+    /// of check only mode (At most [`Severity::Warning`] as changes for rules
+    /// set to error are also added as diagnostics)
+    pub fn pending_change_severity(&self) -> Option<Severity> {
+        self.pending_change_severity
+    }
+
+    /// The most severe thing found during the run. Failures are always
+    /// [`Severity::Error`] and problems in the baseline are not considered.
+    pub fn highest_severity(&self) -> Option<Severity> {
+        let failures = (!self.failures.is_empty()).then_some(Severity::Error);
+        self.new_diagnostics()
+            .map(|x| x.severity)
+            .chain(failures)
+            .chain(self.pending_change_severity)
+            .max()
+    }
+
+    /// Code the process exits with: 0 if nothing needs attention, 1 for errors
+    /// and failures, 2 if files would be changed and 3 for warnings (Decided
+    /// by [`Report::highest_severity`])
+    pub fn exit_code(&self) -> i32 {
+        match self.highest_severity() {
+            Some(Severity::Error) => 1,
+            Some(Severity::Warning) if self.pending_change_severity == Some(Severity::Warning) => 2,
+            Some(Severity::Warning) => 3,
+            Some(Severity::Info | Severity::Off) | None => 0,
+        }
+    }
+
+    /// Marks the problems that `is_known` returns true for as in the baseline
+    /// (They are no longer counted in the stats)
+    pub(crate) fn mark_baselined(&mut self, mut is_known: impl FnMut(&Diagnostic) -> bool) {
+        for diagnostic in &mut self.diagnostics {
+            if diagnostic.is_baselined || !is_known(diagnostic) {
+                continue;
+            }
+            diagnostic.is_baselined = true;
+            if diagnostic.rule.is_seo() {
+                self.stats.dec_seo_warnings();
+            }
+            if diagnostic.severity == Severity::Error {
+                self.stats.dec_errors();
+            }
+        }
+    }
+
+    pub(crate) fn add_failure(&mut self, error: Error) {
+        self.stats.inc_errors();
+        self.failures.push(error);
+    }
+
+    /// `checked_value` is the value the rule found a problem with and is used
+    /// to identify the problem in a baseline
+    pub(crate) fn add_diagnostic(
+        &mut self,
+        path: &Path,
+        rule: Rule,
+        severity: Severity,
+        message: String,
+        checked_value: &str,
+    ) {
+        if rule.is_seo() {
+            self.stats.inc_seo_warnings();
+        }
+        if severity == Severity::Error {
+            self.stats.inc_errors();
+        }
+        self.diagnostics.push(Diagnostic {
+            path: path.to_path_buf(),
+            rule,
+            severity,
+            message,
+            fingerprint: fingerprint(checked_value),
+            is_baselined: false,
+        });
+    }
+
+    pub(crate) fn add_changed(&mut self, path: &Path) {
+        self.stats.inc_changed();
+        self.changed_files.push(path.to_path_buf());
+    }
+
+    pub(crate) fn add_pending_change(&mut self, severity: Severity) {
+        self.pending_change_severity = self.pending_change_severity.max(Some(severity));
+    }
+
+    pub(crate) fn add_suppressions(&mut self, suppressions: Vec<Suppression>) {
+        self.suppressions.extend(suppressions);
+    }
+
+    pub(crate) fn site_files(&self) -> &[SiteFile] {
+        &self.site_files
+    }
+
+    pub(crate) fn add_site_file(&mut self, file: SiteFile) {
+        self.site_files.push(file);
+    }
+
+    pub(crate) fn set_fixed_baseline_entries(&mut self, entries: Vec<BaselineEntry>) {
+        self.fixed_baseline_entries = entries;
+    }
+}
+
+impl AddAssign for Report {
+    fn add_assign(&mut self, rhs: Self) {
+        let Self {
+            stats,
+            failures,
+            diagnostics,
+            changed_files,
+            pending_change_severity,
+            fixed_baseline_entries,
+            suppressions,
+            site_files,
+        } = self;
+        *stats += rhs.stats;
+        failures.extend(rhs.failures);
+        diagnostics.extend(rhs.diagnostics);
+        changed_files.extend(rhs.changed_files);
+        *pending_change_severity = (*pending_change_severity).max(rhs.pending_change_severity);
+        fixed_baseline_entries.extend(rhs.fixed_baseline_entries);
+        suppressions.extend(rhs.suppressions);
+        site_files.extend(rhs.site_files);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add(report: &mut Report, severity: Severity) {
+        report.add_diagnostic(
+            Path::new("a.md"),
+            Rule::SortKeyMissing,
+            severity,
+            "problem".to_string(),
+            "",
+        );
+    }
+
+    #[test]
+    fn exit_codes() {
+        let mut report = Report::new();
+        assert_eq!(report.exit_code(), 0);
+        add(&mut report, Severity::Info);
+        assert_eq!(report.exit_code(), 0);
+        add(&mut report, Severity::Warning);
+        assert_eq!(report.exit_code(), 3);
+        report.add_pending_change(Severity::Warning);
+        assert_eq!(report.exit_code(), 2);
+        add(&mut report, Severity::Error);
+        assert_eq!(report.exit_code(), 1);
+
+        // Only problems that are not in the baseline count
+        report.mark_baselined(|x| x.severity() == Severity::Error);
+        assert_eq!(report.exit_code(), 2);
+        assert_eq!(report.stats().errors(), 0);
+
+        // A fix set to error in check only mode is counted as an error
+        let mut report = Report::new();
+        report.add_diagnostic(
+            Path::new("a.md"),
+            Rule::SeriesNotSet,
+            Severity::Error,
+            "file would be changed to fix it".to_string(),
+            "",
+        );
+        report.add_pending_change(Severity::Warning);
+        assert_eq!(report.exit_code(), 1);
+        assert_eq!(report.stats().errors(), 1);
+        report.mark_baselined(|_| true);
+        assert_eq!(report.exit_code(), 2);
+
+        let mut report = Report::new();
+        report.add_failure(Error::FileExists {
+            path: PathBuf::from("a.md"),
+        });
+        assert_eq!(report.exit_code(), 1);
+    }
+}
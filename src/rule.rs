@@ -0,0 +1,328 @@
+//! Ids and severities of the rules that are checked
+
+use std::{fmt::Display, str::FromStr};
+
+/// The checks (and fixes) that are run on each file
+///
+/// Each has an id used to refer to it in the project config and in a baseline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Rule {
+    /// A section does not set `transparent` or it is not a boolean
+    TransparentNotSet,
+    /// A page (or the config) has no description
+    DescriptionMissing,
+    /// The description is not a string
+    DescriptionNotString,
+    /// The length of the description is outside of the preferred range
+    DescriptionLength,
+    /// The series of a page is not the section name (Fixed automatically)
+    SeriesNotSet,
+    /// The tags of a page do not include the section name (Fixed
+    /// automatically)
+    TagsMissingSection,
+    /// A `disable_check_*` setting in `extra` is not a boolean or is
+    /// misspelled (The setting is ignored)
+    InvalidSetting,
+    /// The title of a page or section is the same as (or similar to) the
+    /// title of another
+    DuplicateTitle,
+    /// The description of a page or section is the same as (or similar to)
+    /// the description of another
+    DuplicateDescription,
+    /// A page or section has the same URL as another page or section
+    UrlCollision,
+    /// An alias is the URL of a page or section or the same as another alias
+    AliasCollision,
+    /// An internal link (`@/`) points to a file that does not exist
+    BrokenLink,
+    /// An internal link points to an anchor that is not produced by any
+    /// heading in the target file
+    BrokenAnchor,
+    /// An image or other local file referenced in the content does not exist
+    MissingAsset,
+    /// An image has no alt text
+    ImageMissingAlt,
+    /// A referenced image is larger than the size limit
+    ImageTooLarge,
+    /// A markdown file in a page bundle that is not `index.md` (It is not
+    /// built as a page)
+    BundleMarkdown,
+    /// A page does not have the key (or it has the wrong type) its section
+    /// sorts pages by (Zola leaves it out of the section's pages)
+    SortKeyMissing,
+    /// A page has the same weight as another page in a section sorted by
+    /// weight
+    DuplicateWeight,
+    /// `date` or `updated` is not a TOML date or date-time
+    InvalidDate,
+    /// `updated` is earlier than `date`
+    UpdatedBeforeDate,
+    /// `date` or `updated` is in the future and the page is not a draft
+    FutureDate,
+    /// The pages of a series use both date-only and date-time dates
+    MixedDateFormats,
+    /// `updated` is not when the body of the page last changed according to
+    /// the git history (Only checked when setting `updated` is enabled)
+    UpdatedOutdated,
+    /// A published page links to a draft (Drafts are not built)
+    LinkToDraft,
+    /// All the pages in a series are drafts (The series is empty when built)
+    DraftOnlySeries,
+}
+
+/// How a rule is treated when it finds a problem
+///
+/// Ordered from least to most severe. The most severe problem found determines
+/// the exit code of the run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The rule is not checked (and its fix is not applied)
+    Off,
+    /// Problems are reported but do not fail the run
+    Info,
+    /// Problems fail the run with the warning exit code
+    Warning,
+    /// Problems fail the run
+    Error,
+}
+
+impl Rule {
+    /// All the rules
+    pub const ALL: [Rule; 26] = [
+        Rule::TransparentNotSet,
+        Rule::DescriptionMissing,
+        Rule::DescriptionNotString,
+        Rule::DescriptionLength,
+        Rule::SeriesNotSet,
+        Rule::TagsMissingSection,
+        Rule::InvalidSetting,
+        Rule::DuplicateTitle,
+        Rule::DuplicateDescription,
+        Rule::UrlCollision,
+        Rule::AliasCollision,
+        Rule::BrokenLink,
+        Rule::BrokenAnchor,
+        Rule::MissingAsset,
+        Rule::ImageMissingAlt,
+        Rule::ImageTooLarge,
+        Rule::BundleMarkdown,
+        Rule::SortKeyMissing,
+        Rule::DuplicateWeight,
+        Rule::InvalidDate,
+        Rule::UpdatedBeforeDate,
+        Rule::FutureDate,
+        Rule::MixedDateFormats,
+        Rule::UpdatedOutdated,
+        Rule::LinkToDraft,
+        Rule::DraftOnlySeries,
+    ];
+
+    /// Id used to refer to the rule
+    pub fn id(&self) -> &'static str {
+        match self {
+            Rule::TransparentNotSet => "transparent-not-set",
+            Rule::DescriptionMissing => "description-missing",
+            Rule::DescriptionNotString => "description-not-string",
+            Rule::DescriptionLength => "description-length",
+            Rule::SeriesNotSet => "series-not-set",
+            Rule::TagsMissingSection => "tags-missing-section",
+            Rule::InvalidSetting => "invalid-setting",
+            Rule::DuplicateTitle => "duplicate-title",
+            Rule::DuplicateDescription => "duplicate-description",
+            Rule::UrlCollision => "url-collision",
+            Rule::AliasCollision => "alias-collision",
+            Rule::BrokenLink => "broken-link",
+            Rule::BrokenAnchor => "broken-anchor",
+            Rule::MissingAsset => "missing-asset",
+            Rule::ImageMissingAlt => "image-missing-alt",
+            Rule::ImageTooLarge => "image-too-large",
+            Rule::BundleMarkdown => "bundle-markdown",
+            Rule::SortKeyMissing => "sort-key-missing",
+            Rule::DuplicateWeight => "duplicate-weight",
+            Rule::InvalidDate => "invalid-date",
+            Rule::UpdatedBeforeDate => "updated-before-date",
+            Rule::FutureDate => "future-date",
+            Rule::MixedDateFormats => "mixed-date-formats",
+            Rule::UpdatedOutdated => "updated-outdated",
+            Rule::LinkToDraft => "link-to-draft",
+            Rule::DraftOnlySeries => "draft-only-series",
+        }
+    }
+
+    /// What the rule checks and how to fix the problems it finds
+    pub fn description(&self) -> &'static str {
+        match self {
+            Rule::TransparentNotSet => {
+                "Sections (`_index.md`) must set `transparent` to true or false. Abridge relies on it to decide if the pages of a section are shown with their parent."
+            }
+            Rule::DescriptionMissing => {
+                "Pages should have a `description` as it is used by search engines and for previews when the page is shared."
+            }
+            Rule::DescriptionNotString => "`description` must be a string.",
+            Rule::DescriptionLength => {
+                "Descriptions should be 140-180 characters long so search engines show them in full."
+            }
+            Rule::SeriesNotSet => {
+                "Pages in a section should have `extra.series` set to the name of the section (Fixed automatically)."
+            }
+            Rule::TagsMissingSection => {
+                "Pages in a section should include the name of the section in `taxonomies.tags` (Fixed automatically)."
+            }
+            Rule::InvalidSetting => "The `disable_check_*` settings in `extra` must be booleans.",
+            Rule::DuplicateTitle => {
+                "Each page should have its own title. Titles that are the same or similar (see the `[duplicates]` threshold) are reported."
+            }
+            Rule::DuplicateDescription => {
+                "Each page should have its own description. Descriptions that are the same or similar (see the `[duplicates]` threshold) are reported."
+            }
+            Rule::UrlCollision => {
+                "Two pages or sections end up at the same URL (from their path, `slug` or `path`) so one replaces the other when the site is built."
+            }
+            Rule::AliasCollision => {
+                "An entry in `aliases` is the URL of another page or alias so one of the redirects is lost."
+            }
+            Rule::BrokenLink => {
+                "An internal link (`@/path.md`) points to a file that does not exist. Zola fails to build the site."
+            }
+            Rule::BrokenAnchor => {
+                "An internal link (`@/path.md#anchor`) points to an anchor that no heading in the target produces."
+            }
+            Rule::MissingAsset => {
+                "An image or file referenced in the content was not found next to the page (Only for page bundles and sections) or in `static/`."
+            }
+            Rule::ImageMissingAlt => {
+                "Images should have alt text for accessibility and search engines."
+            }
+            Rule::ImageTooLarge => {
+                "Images referenced should not be larger than the limit (`max_size_kb` in the `[images]` table, default 500) as they slow down the page."
+            }
+            Rule::BundleMarkdown => {
+                "Markdown files other than `index.md` in a page bundle are not built by zola."
+            }
+            Rule::SortKeyMissing => {
+                "Pages in a section with `sort_by` set must have the key it sorts by (like `date` or `weight`) or zola leaves them out of the section."
+            }
+            Rule::DuplicateWeight => {
+                "Pages in a section sorted by weight should have unique weights or their order is not defined."
+            }
+            Rule::InvalidDate => {
+                "`date` and `updated` must be TOML dates (like 2024-01-31 or 2024-01-31T09:00:00Z) and not strings."
+            }
+            Rule::UpdatedBeforeDate => "`updated` should not be earlier than `date`.",
+            Rule::FutureDate => {
+                "`date` and `updated` should not be in the future unless the page is a draft."
+            }
+            Rule::MixedDateFormats => {
+                "The pages of a series should all use date-only or all use date-time dates."
+            }
+            Rule::UpdatedOutdated => {
+                "`updated` should be the date of the last commit that changed the body of the page (Only checked and fixed with `--set-updated`)."
+            }
+            Rule::LinkToDraft => {
+                "Published pages should not link to drafts as drafts are not built."
+            }
+            Rule::DraftOnlySeries => {
+                "A series where all the pages are drafts is empty when the site is built."
+            }
+        }
+    }
+
+    /// Severity used if none is configured
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            Rule::TransparentNotSet
+            | Rule::UrlCollision
+            | Rule::AliasCollision
+            | Rule::BrokenLink
+            | Rule::BrokenAnchor
+            | Rule::LinkToDraft => Severity::Error,
+            Rule::DescriptionMissing
+            | Rule::DescriptionNotString
+            | Rule::DescriptionLength
+            | Rule::SeriesNotSet
+            | Rule::TagsMissingSection
+            | Rule::InvalidSetting
+            | Rule::DuplicateTitle
+            | Rule::DuplicateDescription
+            | Rule::MissingAsset
+            | Rule::ImageMissingAlt
+            | Rule::ImageTooLarge
+            | Rule::BundleMarkdown
+            | Rule::SortKeyMissing
+            | Rule::DuplicateWeight
+            | Rule::InvalidDate
+            | Rule::UpdatedBeforeDate
+            | Rule::FutureDate
+            | Rule::MixedDateFormats
+            | Rule::UpdatedOutdated
+            | Rule::DraftOnlySeries => Severity::Warning,
+        }
+    }
+
+    /// True for the rules that are related to SEO
+    pub fn is_seo(&self) -> bool {
+        matches!(
+            self,
+            Rule::DescriptionMissing
+                | Rule::DescriptionNotString
+                | Rule::DescriptionLength
+                | Rule::DuplicateTitle
+                | Rule::DuplicateDescription
+                | Rule::ImageMissingAlt
+        )
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id())
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Rule::ALL
+            .into_iter()
+            .find(|rule| rule.id() == s)
+            .ok_or_else(|| format!("unknown rule {s:?}"))
+    }
+}
+
+impl Severity {
+    /// Name used in the project config
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Off => "off",
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "off" => Severity::Off,
+            "info" => Severity::Info,
+            "warning" => Severity::Warning,
+            "error" => Severity::Error,
+            _ => {
+                return Err(format!(
+                    "unknown severity {s:?} (expected one of: off, info, warning, error)"
+                ));
+            }
+        })
+    }
+}
@@ -0,0 +1,190 @@
+//! Creates the project config and new pages
+
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use toml_edit::{DocumentMut, value};
+
+use crate::{
+    Options, PREFERRED_RANGE, PROJECT_CONFIG_FILE,
+    dates::today,
+    error::{Error, Result},
+    processing::{find_section_info, set_series, set_tags},
+    rule::Rule,
+};
+
+/// Writes a project config for the site at `root_path` with every setting
+/// commented out at its default. Returns the path of the file written.
+///
+/// Fails if the file already exists unless `force` is set
+pub fn init_project_config(root_path: &Path, force: bool) -> Result<PathBuf> {
+    let path = root_path.join(PROJECT_CONFIG_FILE);
+    if !force && path.exists() {
+        return Err(Error::FileExists { path });
+    }
+    let mut text = String::from(
+        "# Settings for zola_abridge_helper (Uncomment a line to change the default)\n\n[rules]\n",
+    );
+    for rule in Rule::ALL {
+        let _ = writeln!(text, "# {rule} = \"{}\"", rule.default_severity());
+    }
+    text.push_str(
+        r#"
+[duplicates]
+# threshold = 90 # Percent similarity at which values are reported
+
+[images]
+# max_size_kb = 500 # Size above which referenced images are reported
+
+[files]
+# include = ["blog/**"] # Globs relative to the content folder
+# exclude = ["blog/drafts/**"]
+# gitignore = true # Respect `.gitignore`
+# hidden = false # Process hidden files and folders
+# symlinks = "follow" # Or "skip" or "error"
+
+[drafts]
+# policy = "check" # Or "skip", "no-fail" or "fix-only"
+"#,
+    );
+    fs::write(&path, text).map_err(|e| Error::io(&path, "Failed to write project config", e))?;
+    Ok(path)
+}
+
+/// Creates a page at `page_path` dated today with the series and tags of the
+/// section it is in (Folders are created as needed)
+///
+/// The title defaults to the file name with dashes and underscores replaced by
+/// spaces. The description is left as a comment to fill in (An empty one would
+/// hide that it is missing). Fails if the file already exists.
+pub fn new_page(page_path: &Path, title: Option<&str>, draft: bool) -> Result<()> {
+    if page_path.exists() {
+        return Err(Error::FileExists {
+            path: page_path.to_path_buf(),
+        });
+    }
+    let title = match title {
+        Some(title) => title.to_string(),
+        None => default_title(page_path),
+    };
+    let mut doc = DocumentMut::new();
+    doc["title"] = value(title);
+    doc["date"] = value(today());
+    // Left for the author to fill in so it is reported as missing until then
+    if let Some(mut key) = doc.as_table_mut().key_mut("date") {
+        key.leaf_decor_mut().set_prefix(format!(
+            "# description = \"\" # Between {} and {} characters\n",
+            PREFERRED_RANGE.start(),
+            PREFERRED_RANGE.end()
+        ));
+    }
+    if draft {
+        doc["draft"] = value(true);
+    }
+
+    let page_path = std::path::absolute(page_path)
+        .map_err(|e| Error::io(page_path, "Failed to resolve path", e))?;
+    let root_path = page_path
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.join("config.toml").is_file());
+    if let Some(root_path) = root_path {
+        let options = Options::new(root_path).with_project_config(root_path)?;
+        let content_root = root_path.join("content");
+        if let Some(section_info) = find_section_info(&content_root, &page_path, &options)? {
+            set_series(&mut doc, &section_info);
+            set_tags(&mut doc, &section_info);
+        }
+    }
+
+    if let Some(parent) = page_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| Error::io(parent, "Failed to create folder", e))?;
+    }
+    fs::write(&page_path, format!("+++\n{doc}+++\n"))
+        .map_err(|e| Error::io(&page_path, "Failed to write page", e))
+}
+
+/// The file name of `path` with dashes and underscores replaced by spaces and
+/// the first letter in upper case
+fn default_title(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|x| x.to_string_lossy().replace(['-', '_'], " "))
+        .unwrap_or_default();
+    let mut chars = stem.trim().chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = init_project_config(dir.path(), false).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.contains("# description-length = \"warning\"\n"));
+        assert!(text.contains("[drafts]\n# policy = \"check\""));
+        // Every line is commented out so it loads as the defaults
+        assert_eq!(
+            Options::new(dir.path())
+                .with_project_config(dir.path())
+                .unwrap(),
+            Options::new(dir.path())
+        );
+
+        fs::write(&path, "changed").unwrap();
+        assert!(matches!(
+            init_project_config(dir.path(), false),
+            Err(Error::FileExists { .. })
+        ));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "changed");
+        init_project_config(dir.path(), true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), text);
+    }
+
+    #[test]
+    fn page_in_section() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("config.toml"), "").unwrap();
+        let section = dir.path().join("content/rust");
+        fs::create_dir_all(&section).unwrap();
+        fs::write(
+            section.join("_index.md"),
+            "+++\ntitle = \"Rust\"\ntransparent = false\n+++\n",
+        )
+        .unwrap();
+
+        let path = section.join("new/my-first_post.md");
+        new_page(&path, None, true).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        let expected = format!(
+            "+++\ntitle = \"My first post\"\n# description = \"\" # Between 140 and 180 characters\ndate = {}\ndraft = true\nextra = {{ series = \"Rust\" }}\ntaxonomies = {{ tags = [\"Rust\"] }}\n+++\n",
+            today()
+        );
+        assert_eq!(text, expected);
+
+        assert!(matches!(
+            new_page(&path, Some("Other"), false),
+            Err(Error::FileExists { .. })
+        ));
+        assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+    }
+
+    #[test]
+    fn page_outside_site() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("post.md");
+        new_page(&path, Some("Hello"), false).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with("+++\ntitle = \"Hello\"\n# description"));
+        assert!(!text.contains("series") && !text.contains("draft"));
+    }
+}
@@ -1,6 +1,197 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::BTreeSet, path::PathBuf};
 
-use crate::TOML_KEY_EXTRA;
+use crate::{TOML_KEY_EXTRA, dates::as_date, rule::Rule};
+
+/// Keys in `extra` that disable checks
+pub const SETTING_KEYS: [&str; 3] = [
+    "disable_check_series",
+    "disable_check_tag",
+    "disable_check_description",
+];
+
+/// Most edits a key can be away from a known key and still be treated as a
+/// misspelling of it
+const MAX_TYPO_DISTANCE: usize = 3;
+
+/// Problems with the keys in `extra` used to disable checks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SettingIssue {
+    /// A known key that is set to a value that is not a boolean (The value is
+    /// ignored)
+    NotBool { key: String, value: String },
+    /// A key that looks like it was meant to be one of the known keys
+    UnknownKey {
+        key: String,
+        suggestion: &'static str,
+    },
+}
+
+impl SettingIssue {
+    /// The key the issue is with
+    pub fn key(&self) -> &str {
+        match self {
+            SettingIssue::NotBool { key, .. } | SettingIssue::UnknownKey { key, .. } => key,
+        }
+    }
+}
+
+impl std::fmt::Display for SettingIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingIssue::NotBool { key, value } => write!(
+                f,
+                "`{TOML_KEY_EXTRA}.{key}` is ignored because it is not a boolean (true or false). Value found: {value}"
+            ),
+            SettingIssue::UnknownKey { key, suggestion } => write!(
+                f,
+                "unknown setting `{TOML_KEY_EXTRA}.{key}` is ignored, did you mean `{suggestion}`?"
+            ),
+        }
+    }
+}
+
+/// Finds the keys in `extra` used to disable checks that are not booleans and
+/// the keys that look like misspellings of them
+pub fn find_setting_issues(doc: &toml_edit::Table) -> Vec<SettingIssue> {
+    let Some(extra) = doc.get(TOML_KEY_EXTRA).and_then(|x| x.as_table_like()) else {
+        return Vec::new();
+    };
+    let mut result = Vec::new();
+    for (key, value) in extra.iter() {
+        if SETTING_KEYS.contains(&key) {
+            if !value.is_bool() {
+                result.push(SettingIssue::NotBool {
+                    key: key.to_string(),
+                    value: value.to_string().trim().to_string(),
+                });
+            }
+            continue;
+        }
+        let Some((distance, suggestion)) = SETTING_KEYS
+            .iter()
+            .map(|known| (strsim::levenshtein(key, known), *known))
+            .min()
+        else {
+            continue;
+        };
+        if key.starts_with("disable_check") || distance <= MAX_TYPO_DISTANCE {
+            result.push(SettingIssue::UnknownKey {
+                key: key.to_string(),
+                suggestion,
+            });
+        }
+    }
+    result
+}
+
+/// Values of `sort_by` for a section that need a key to be set in each page
+/// (Pages without it are left out of the section's pages by zola)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    Date,
+    UpdateDate,
+    Title,
+    TitleBytes,
+    Weight,
+}
+
+/// Problems with the key a page needs for the section to sort it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortKeyIssue {
+    Missing { sort_by: SortBy },
+    WrongType { sort_by: SortBy },
+}
+
+impl SortBy {
+    /// Reads `sort_by` from the front matter of a section (None if it is not
+    /// set or does not need a key in the pages)
+    pub fn from_section(doc: &toml_edit::Table) -> Option<Self> {
+        match doc.get("sort_by")?.as_str()? {
+            "date" => Some(SortBy::Date),
+            "update_date" => Some(SortBy::UpdateDate),
+            "title" => Some(SortBy::Title),
+            "title_bytes" => Some(SortBy::TitleBytes),
+            "weight" => Some(SortBy::Weight),
+            _ => None,
+        }
+    }
+
+    /// Value of `sort_by` in the section
+    pub fn name(&self) -> &'static str {
+        match self {
+            SortBy::Date => "date",
+            SortBy::UpdateDate => "update_date",
+            SortBy::Title => "title",
+            SortBy::TitleBytes => "title_bytes",
+            SortBy::Weight => "weight",
+        }
+    }
+
+    /// Key the page needs (For `update_date` either `updated` or `date` is
+    /// used)
+    pub fn key(&self) -> &'static str {
+        match self {
+            SortBy::Date => "date",
+            SortBy::UpdateDate => "updated",
+            SortBy::Title | SortBy::TitleBytes => "title",
+            SortBy::Weight => "weight",
+        }
+    }
+
+    /// Checks that `page` has the key needed to be sorted
+    pub fn find_issue(&self, page: &toml_edit::Table) -> Option<SortKeyIssue> {
+        let value = match self {
+            SortBy::UpdateDate => page.get("updated").or_else(|| page.get("date")),
+            _ => page.get(self.key()),
+        };
+        let Some(value) = value else {
+            return Some(SortKeyIssue::Missing { sort_by: *self });
+        };
+        let is_valid = match self {
+            SortBy::Date | SortBy::UpdateDate => as_date(value).is_some(),
+            SortBy::Title | SortBy::TitleBytes => value.is_str(),
+            SortBy::Weight => value.is_integer(),
+        };
+        (!is_valid).then_some(SortKeyIssue::WrongType { sort_by: *self })
+    }
+}
+
+impl SortKeyIssue {
+    /// Key the issue is with
+    pub fn key(&self) -> &'static str {
+        match self {
+            SortKeyIssue::Missing { sort_by } | SortKeyIssue::WrongType { sort_by } => {
+                sort_by.key()
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for SortKeyIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortKeyIssue::Missing { sort_by } => write!(
+                f,
+                "section sorts by `{}` but `{}` is not set, zola leaves the page out of the section's pages",
+                sort_by.name(),
+                sort_by.key()
+            ),
+            SortKeyIssue::WrongType { sort_by } => {
+                let expected = match sort_by {
+                    SortBy::Date | SortBy::UpdateDate => "a date",
+                    SortBy::Title | SortBy::TitleBytes => "a string",
+                    SortBy::Weight => "an integer",
+                };
+                write!(
+                    f,
+                    "section sorts by `{}` but `{}` is not {expected}, zola leaves the page out of the section's pages",
+                    sort_by.name(),
+                    sort_by.key()
+                )
+            }
+        }
+    }
+}
 
 /// Note: both `series` and `tag` must use same set of values
 #[derive(Debug, Clone)]
@@ -10,6 +201,12 @@ pub struct SectionInfo {
     pub disable_check_series: bool,
     pub disable_check_tag: bool,
     pub disable_check_description: bool,
+    /// Rules turned off by suppression comments
+    pub suppressed: BTreeSet<Rule>,
+    /// How the section sorts its pages (if the pages need a key for it)
+    pub sort_by: Option<SortBy>,
+    /// Path of the section file (`_index.md`) the info was read from
+    pub path: Option<PathBuf>,
 }
 
 impl SectionInfo {
@@ -20,6 +217,9 @@ impl SectionInfo {
             disable_check_series: false,
             disable_check_tag: false,
             disable_check_description: false,
+            suppressed: BTreeSet::new(),
+            sort_by: None,
+            path: None,
         }
     }
 
@@ -28,7 +228,11 @@ impl SectionInfo {
         self.title.as_ref().unwrap_or(&self.folder_name)
     }
 
-    pub fn load_settings(&self, doc: &toml_edit::DocumentMut) -> Cow<Self> {
+    pub fn is_suppressed(&self, rule: Rule) -> bool {
+        self.suppressed.contains(&rule)
+    }
+
+    pub fn load_settings(&self, doc: &toml_edit::Table) -> Cow<'_, Self> {
         let mut result = Cow::Borrowed(self);
 
         let Some(extra) = doc.get(TOML_KEY_EXTRA) else {
@@ -37,24 +241,79 @@ impl SectionInfo {
 
         let get_bool = |key_name: &str| extra.get(key_name).and_then(|x| x.as_bool());
 
-        if let Some(disable_check_series) = get_bool("disable_check_series") {
-            if disable_check_series != result.disable_check_series {
-                result.to_mut().disable_check_series = disable_check_series;
-            }
+        if let Some(disable_check_series) = get_bool("disable_check_series")
+            && disable_check_series != result.disable_check_series
+        {
+            result.to_mut().disable_check_series = disable_check_series;
         }
 
-        if let Some(disable_check_tag) = get_bool("disable_check_tag") {
-            if disable_check_tag != result.disable_check_tag {
-                result.to_mut().disable_check_tag = disable_check_tag;
-            }
+        if let Some(disable_check_tag) = get_bool("disable_check_tag")
+            && disable_check_tag != result.disable_check_tag
+        {
+            result.to_mut().disable_check_tag = disable_check_tag;
         }
 
-        if let Some(disable_check_description) = get_bool("disable_check_description") {
-            if disable_check_description != result.disable_check_description {
-                result.to_mut().disable_check_description = disable_check_description;
-            }
+        if let Some(disable_check_description) = get_bool("disable_check_description")
+            && disable_check_description != result.disable_check_description
+        {
+            result.to_mut().disable_check_description = disable_check_description;
         }
 
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setting_issues() {
+        let doc = r#"
+[extra]
+disable_check_description = "true"
+disable_check_serie = true
+disable_check_tag = false
+toc = true
+"#
+        .parse::<toml_edit::DocumentMut>()
+        .unwrap();
+        let issues = find_setting_issues(&doc);
+        assert_eq!(
+            issues,
+            vec![
+                SettingIssue::NotBool {
+                    key: "disable_check_description".to_string(),
+                    value: "\"true\"".to_string()
+                },
+                SettingIssue::UnknownKey {
+                    key: "disable_check_serie".to_string(),
+                    suggestion: "disable_check_series"
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_key_issues() {
+        let section = "sort_by = \"weight\""
+            .parse::<toml_edit::DocumentMut>()
+            .unwrap();
+        let sort_by = SortBy::from_section(&section).unwrap();
+        let page = |text: &str| text.parse::<toml_edit::DocumentMut>().unwrap();
+        assert_eq!(sort_by.find_issue(&page("weight = 2")), None);
+        assert_eq!(
+            sort_by.find_issue(&page("weight = \"2\"")),
+            Some(SortKeyIssue::WrongType { sort_by })
+        );
+        assert_eq!(
+            sort_by.find_issue(&page("")),
+            Some(SortKeyIssue::Missing { sort_by })
+        );
+        assert_eq!(
+            SortBy::UpdateDate.find_issue(&page("date = 2023-01-01")),
+            None
+        );
+        assert_eq!(SortBy::from_section(&page("sort_by = \"none\"")), None);
+    }
+}
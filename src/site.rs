@@ -0,0 +1,608 @@
+//! Checks that need information from every file in the site and are run after
+//! the walk
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use toml_edit::DocumentMut;
+
+use crate::{
+    DraftPolicy, Options, Report, TOML_KEY_EXTRA,
+    dates::{DateFormat, date_format},
+    processing::log_diagnostic,
+    rule::{Rule, Severity},
+};
+
+mod assets;
+mod links;
+mod urls;
+
+/// What is kept about each file processed for the site wide checks
+#[derive(Debug, Clone)]
+pub(crate) struct SiteFile {
+    pub(crate) path: PathBuf,
+    /// The front matter after any fixes were applied
+    pub(crate) front_matter: DocumentMut,
+    /// The markdown after the front matter
+    pub(crate) content: String,
+    /// Line in the file that the content starts on
+    pub(crate) content_start_line: usize,
+    /// Rules suppressed for the file (Including those suppressed for its
+    /// section)
+    pub(crate) suppressed: BTreeSet<Rule>,
+    /// Path of the section file (`_index.md`) of the section a page is in
+    pub(crate) section: Option<PathBuf>,
+    /// True for pages with `draft = true`
+    pub(crate) is_draft: bool,
+}
+
+impl SiteFile {
+    /// The severity `rule` is checked at for the file (Off if the rule is
+    /// suppressed for the file, see [`Options::draft_severity`] for drafts)
+    fn severity(&self, rule: Rule, options: &Options) -> Severity {
+        if self.suppressed.contains(&rule) {
+            return Severity::Off;
+        }
+        let severity = options.rule_severity(rule);
+        if self.is_draft {
+            options.draft_severity(rule, severity)
+        } else {
+            severity
+        }
+    }
+
+    fn get_str(&self, key: &str) -> Option<&str> {
+        self.front_matter.get(key).and_then(|x| x.as_str())
+    }
+}
+
+/// Runs the checks that compare files with each other
+pub(crate) fn check_site(root_path: &Path, files: &[SiteFile], options: &Options) -> Report {
+    let mut result = Report::new();
+    result += check_draft_only_series(files, options);
+    // Skipped drafts are only kept to know which series have published pages
+    let published: Vec<SiteFile>;
+    let files = if options.get_drafts() == DraftPolicy::Skip {
+        published = files.iter().filter(|x| !x.is_draft).cloned().collect();
+        &published
+    } else {
+        files
+    };
+    result += check_duplicates(root_path, files, options, "title", Rule::DuplicateTitle);
+    result += check_duplicates(
+        root_path,
+        files,
+        options,
+        "description",
+        Rule::DuplicateDescription,
+    );
+    result += urls::check_urls(root_path, files, options);
+    result += links::check_links(root_path, files, options);
+    result += assets::check_assets(root_path, files, options);
+    result += check_duplicate_weights(root_path, files, options);
+    result += check_series_date_formats(files, options);
+    result
+}
+
+/// Reports the files where the value of `key` is the same as (or similar to)
+/// the value in other files
+///
+/// Values are compared ignoring case and differences in whitespace. Values are
+/// similar if the similarity of their letter pairs ([Sørensen–Dice]) is at
+/// least the duplicate threshold.
+///
+/// [Sørensen–Dice]: https://en.wikipedia.org/wiki/Dice-S%C3%B8rensen_coefficient
+fn check_duplicates(
+    root_path: &Path,
+    files: &[SiteFile],
+    options: &Options,
+    key: &str,
+    rule: Rule,
+) -> Report {
+    let mut result = Report::new();
+    let severity = options.rule_severity(rule);
+    if severity == Severity::Off {
+        return result;
+    }
+    let threshold = f64::from(options.get_duplicate_threshold()) / 100.0;
+    let values: Vec<(&SiteFile, String)> = files
+        .iter()
+        .filter_map(|file| {
+            let value = normalize(file.get_str(key)?);
+            (!value.is_empty()).then_some((file, value))
+        })
+        .collect();
+
+    for (i, (file, value)) in values.iter().enumerate() {
+        let severity = file.severity(rule, options);
+        if severity == Severity::Off {
+            continue;
+        }
+        let mut same = Vec::new();
+        let mut similar = Vec::new();
+        for (j, (other, other_value)) in values.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            if value == other_value {
+                same.push(relative(root_path, &other.path));
+            } else if is_length_similar(value, other_value, threshold) {
+                let similarity = strsim::sorensen_dice(value, other_value);
+                if similarity >= threshold {
+                    similar.push(format!(
+                        "{} ({:.0}%)",
+                        relative(root_path, &other.path),
+                        similarity * 100.0
+                    ));
+                }
+            }
+        }
+        if same.is_empty() && similar.is_empty() {
+            continue;
+        }
+        let mut message = format!("{key} is not unique.");
+        if !same.is_empty() {
+            message.push_str(&format!(" Same as: {}.", same.join(", ")));
+        }
+        if !similar.is_empty() {
+            message.push_str(&format!(" Similar to: {}.", similar.join(", ")));
+        }
+        log_diagnostic(
+            severity,
+            rule,
+            &format!("{message} In file at: {:?}", file.path),
+        );
+        result.add_diagnostic(&file.path, rule, severity, message, value);
+    }
+    result
+}
+
+/// Reports pages with the same weight as another page in a section that sorts
+/// its pages by weight (The order of those pages is not defined)
+fn check_duplicate_weights(root_path: &Path, files: &[SiteFile], options: &Options) -> Report {
+    let mut result = Report::new();
+    let rule = Rule::DuplicateWeight;
+    let severity = options.rule_severity(rule);
+    if severity == Severity::Off {
+        return result;
+    }
+    let sorted_by_weight: BTreeSet<&Path> = files
+        .iter()
+        .filter(|file| file.get_str("sort_by") == Some("weight"))
+        .map(|file| file.path.as_path())
+        .collect();
+    let mut weights: BTreeMap<(&Path, i64), Vec<&SiteFile>> = BTreeMap::new();
+    for file in files {
+        let Some(section) = file
+            .section
+            .as_deref()
+            .filter(|x| sorted_by_weight.contains(x))
+        else {
+            continue;
+        };
+        if let Some(weight) = file.front_matter.get("weight").and_then(|x| x.as_integer()) {
+            weights.entry((section, weight)).or_default().push(file);
+        }
+    }
+    for ((_, weight), pages) in weights.iter().filter(|(_, pages)| pages.len() > 1) {
+        for page in pages {
+            let severity = page.severity(rule, options);
+            if severity == Severity::Off {
+                continue;
+            }
+            let others: Vec<_> = pages
+                .iter()
+                .filter(|other| other.path != page.path)
+                .map(|other| relative(root_path, &other.path))
+                .collect();
+            let message = format!(
+                "weight {weight} is also used by: {} (Section is sorted by weight)",
+                others.join(", ")
+            );
+            log_diagnostic(
+                severity,
+                rule,
+                &format!("{message}. In file at: {:?}", page.path),
+            );
+            result.add_diagnostic(&page.path, rule, severity, message, &weight.to_string());
+        }
+    }
+    result
+}
+
+/// Reports pages in a series whose `date` format (date-only or date-time) is
+/// not the one most used in the series (All the pages if it is a tie)
+fn check_series_date_formats(files: &[SiteFile], options: &Options) -> Report {
+    let mut result = Report::new();
+    let rule = Rule::MixedDateFormats;
+    let severity = options.rule_severity(rule);
+    if severity == Severity::Off {
+        return result;
+    }
+    let mut series: BTreeMap<&str, Vec<(&SiteFile, DateFormat)>> = BTreeMap::new();
+    for file in files {
+        let name = file
+            .front_matter
+            .get(TOML_KEY_EXTRA)
+            .and_then(|x| x.get("series"))
+            .and_then(|x| x.as_str());
+        if let Some(name) = name
+            && let Some(format) = date_format(&file.front_matter)
+        {
+            series.entry(name).or_default().push((file, format));
+        }
+    }
+    for (name, pages) in series {
+        let date_only = pages
+            .iter()
+            .filter(|(_, format)| *format == DateFormat::DateOnly)
+            .count();
+        let date_time = pages.len() - date_only;
+        if date_only == 0 || date_time == 0 {
+            continue;
+        }
+        let is_reported = |format: DateFormat| match format {
+            DateFormat::DateOnly => date_only <= date_time,
+            DateFormat::DateTime => date_time <= date_only,
+        };
+        for (page, format) in pages {
+            let severity = page.severity(rule, options);
+            if !is_reported(format) || severity == Severity::Off {
+                continue;
+            }
+            let message = format!(
+                "series {name:?} mixes date-only and date-time dates ({date_only} date-only, {date_time} date-time)"
+            );
+            log_diagnostic(
+                severity,
+                rule,
+                &format!("{message}. In file at: {:?}", page.path),
+            );
+            let checked_value = page
+                .front_matter
+                .get("date")
+                .map(|x| x.to_string())
+                .unwrap_or_default();
+            result.add_diagnostic(&page.path, rule, severity, message, checked_value.trim());
+        }
+    }
+    result
+}
+
+/// Reports series where all the pages are drafts (The series has no pages when
+/// the site is built)
+///
+/// Reported on the section of the pages (or the pages if they are not in a
+/// section)
+fn check_draft_only_series(files: &[SiteFile], options: &Options) -> Report {
+    let mut result = Report::new();
+    let rule = Rule::DraftOnlySeries;
+    let severity = options.rule_severity(rule);
+    if severity == Severity::Off {
+        return result;
+    }
+    let sections: BTreeMap<&Path, &SiteFile> = files
+        .iter()
+        .map(|file| (file.path.as_path(), file))
+        .collect();
+    let mut series: BTreeMap<&str, Vec<&SiteFile>> = BTreeMap::new();
+    for file in files.iter().filter(|x| !x.path.ends_with("_index.md")) {
+        // Pages that were not fixed (like skipped drafts) are taken to be in
+        // the series of their section
+        let name = file
+            .front_matter
+            .get(TOML_KEY_EXTRA)
+            .and_then(|x| x.get("series"))
+            .and_then(|x| x.as_str())
+            .or_else(|| {
+                let section = sections.get(file.section.as_deref()?)?;
+                section
+                    .get_str("title")
+                    .or_else(|| section.path.parent()?.file_name()?.to_str())
+            });
+        if let Some(name) = name {
+            series.entry(name).or_default().push(file);
+        }
+    }
+    for (name, pages) in series {
+        if !pages.iter().all(|page| page.is_draft) {
+            continue;
+        }
+        let mut reported = BTreeSet::new();
+        for page in pages {
+            let file = page
+                .section
+                .as_deref()
+                .and_then(|x| sections.get(x).copied())
+                .unwrap_or(page);
+            if !reported.insert(file.path.as_path()) || file.suppressed.contains(&rule) {
+                continue;
+            }
+            let message = format!("series {name:?} only has drafts (It is empty when built)");
+            log_diagnostic(
+                severity,
+                rule,
+                &format!("{message}. In file at: {:?}", file.path),
+            );
+            result.add_diagnostic(&file.path, rule, severity, message, name);
+        }
+    }
+    result
+}
+
+/// Lower case with all whitespace replaced by a single space
+fn normalize(value: &str) -> String {
+    value
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Values with very different lengths cannot be similar. Checked first as it
+/// is much cheaper than calculating the similarity.
+///
+/// The similarity is at most `2r / (1 + r)` where `r` is the ratio of the
+/// lengths, so `r` must be at least `t / (2 - t)` for a threshold of `t`.
+fn is_length_similar(a: &str, b: &str, threshold: f64) -> bool {
+    let (a, b) = (a.len() as f64, b.len() as f64);
+    a.min(b) / a.max(b) >= threshold / (2.0 - threshold)
+}
+
+/// Path relative to the root of the site for messages
+fn relative(root_path: &Path, path: &Path) -> String {
+    path.strip_prefix(root_path)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// The lines (and their index) that are not in fenced code blocks
+fn lines_outside_code(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut fence: Option<String> = None;
+    content.lines().enumerate().filter(move |(_, line)| {
+        let trimmed = line.trim_start();
+        let marker: String = trimmed
+            .chars()
+            .take_while(|c| *c == '`' || *c == '~')
+            .collect();
+        match &fence {
+            Some(open) => {
+                if marker.starts_with(open.as_str()) && trimmed[marker.len()..].trim().is_empty() {
+                    fence = None;
+                }
+                false
+            }
+            None if marker.len() >= 3 && !(marker.contains('`') && marker.contains('~')) => {
+                fence = Some(marker);
+                false
+            }
+            None => true,
+        }
+    })
+}
+
+/// Replaces the text of inline code spans with spaces so links in them are not
+/// matched (Keeps the positions of the rest of the line)
+fn strip_inline_code(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut in_code = false;
+    for c in line.chars() {
+        if c == '`' {
+            in_code = !in_code;
+            result.push(c);
+        } else if in_code {
+            result.extend(std::iter::repeat_n(' ', c.len_utf8()));
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A published page at `path` with no content (Shared by the tests of the
+    /// checks in the submodules)
+    pub(super) fn site_file(path: impl Into<PathBuf>, front_matter: &str) -> SiteFile {
+        SiteFile {
+            path: path.into(),
+            front_matter: front_matter.parse().unwrap(),
+            content: String::new(),
+            content_start_line: 1,
+            suppressed: BTreeSet::new(),
+            is_draft: false,
+            section: None,
+        }
+    }
+
+    #[test]
+    fn duplicates() {
+        let files = [
+            site_file("a.md", "title = \"Intro to Rust, part one\""),
+            site_file("b.md", "title = \"Intro to  rust, part one\""),
+            site_file("c.md", "title = \"Intro to Rust, part one.\""),
+            site_file("d.md", "title = \"Something else\""),
+        ];
+        let options = Options::new(".");
+        let report = check_site(Path::new("."), &files, &options);
+        let paths: Vec<_> = report
+            .diagnostics()
+            .iter()
+            .map(|x| x.path().to_str().unwrap())
+            .collect();
+        assert_eq!(paths, ["a.md", "b.md", "c.md"]);
+        assert!(report.diagnostics()[0].message().contains("Same as: b.md."));
+        assert!(
+            report.diagnostics()[0]
+                .message()
+                .contains("Similar to: c.md")
+        );
+    }
+
+    #[test]
+    fn duplicate_weights() {
+        let page = |path: &str, front_matter: &str, section: &str| SiteFile {
+            section: Some(PathBuf::from(section)),
+            ..site_file(path, front_matter)
+        };
+        let files = [
+            site_file("blog/_index.md", "sort_by = \"weight\""),
+            page("blog/a.md", "weight = 1", "blog/_index.md"),
+            page("blog/b.md", "weight = 1", "blog/_index.md"),
+            page("blog/c.md", "weight = 2", "blog/_index.md"),
+            SiteFile {
+                suppressed: BTreeSet::from([Rule::DuplicateWeight]),
+                ..page("blog/d.md", "weight = 2", "blog/_index.md")
+            },
+            site_file("notes/_index.md", "sort_by = \"date\""),
+            page("notes/a.md", "weight = 1", "notes/_index.md"),
+            page("notes/b.md", "weight = 1", "notes/_index.md"),
+        ];
+        let report = check_duplicate_weights(Path::new("."), &files, &Options::new("."));
+        let found: Vec<_> = report
+            .diagnostics()
+            .iter()
+            .map(|x| (x.path().to_str().unwrap(), x.message()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    "blog/a.md",
+                    "weight 1 is also used by: blog/b.md (Section is sorted by weight)"
+                ),
+                (
+                    "blog/b.md",
+                    "weight 1 is also used by: blog/a.md (Section is sorted by weight)"
+                ),
+                (
+                    "blog/c.md",
+                    "weight 2 is also used by: blog/d.md (Section is sorted by weight)"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn series_date_formats() {
+        let page = |path: &str, series: &str, date: &str| {
+            site_file(
+                path,
+                &format!("date = {date}\nextra = {{ series = \"{series}\" }}"),
+            )
+        };
+        let files = [
+            page("rust/a.md", "Rust", "2024-01-01"),
+            page("rust/b.md", "Rust", "2024-01-02"),
+            page("rust/c.md", "Rust", "2024-01-03T10:00:00Z"),
+            page("go/a.md", "Go", "2024-01-01"),
+            page("go/b.md", "Go", "2024-01-02T10:00:00Z"),
+            page("c/a.md", "C", "2024-01-01"),
+            page("c/b.md", "C", "2024-01-02"),
+        ];
+        let report = check_series_date_formats(&files, &Options::new("."));
+        let paths: Vec<_> = report
+            .diagnostics()
+            .iter()
+            .map(|x| x.path().to_str().unwrap())
+            .collect();
+        assert_eq!(paths, ["go/a.md", "go/b.md", "rust/c.md"]);
+        assert_eq!(
+            report.diagnostics()[2].message(),
+            "series \"Rust\" mixes date-only and date-time dates (2 date-only, 1 date-time)"
+        );
+    }
+
+    #[test]
+    fn draft_only_series() {
+        let page = |path: &str, is_draft: bool, section: &str| SiteFile {
+            is_draft,
+            section: Some(PathBuf::from(section)),
+            ..site_file(path, "")
+        };
+        let files = [
+            site_file("rust/_index.md", "title = \"Rust\""),
+            page("rust/a.md", true, "rust/_index.md"),
+            page("rust/b.md", true, "rust/_index.md"),
+            site_file("go/_index.md", "title = \"Go\""),
+            page("go/a.md", true, "go/_index.md"),
+            page("go/b.md", false, "go/_index.md"),
+            SiteFile {
+                suppressed: BTreeSet::from([Rule::DraftOnlySeries]),
+                ..site_file("c/_index.md", "title = \"C\"")
+            },
+            page("c/a.md", true, "c/_index.md"),
+        ];
+        let report = check_draft_only_series(&files, &Options::new("."));
+        let found: Vec<_> = report
+            .diagnostics()
+            .iter()
+            .map(|x| (x.path().to_str().unwrap(), x.message()))
+            .collect();
+        assert_eq!(
+            found,
+            [(
+                "rust/_index.md",
+                "series \"Rust\" only has drafts (It is empty when built)"
+            )]
+        );
+    }
+
+    #[test]
+    fn draft_policies() {
+        let draft = |path: &str, front_matter: &str| SiteFile {
+            is_draft: true,
+            ..site_file(path, front_matter)
+        };
+        let files = [
+            site_file("a.md", "title = \"Same\""),
+            draft("b.md", "title = \"Same\""),
+            draft("c.md", "date = 2024-01-01\nextra = { series = \"X\" }"),
+            draft(
+                "d.md",
+                "date = 2024-01-02T10:00:00Z\nextra = { series = \"X\" }",
+            ),
+            site_file("e.md", "extra = { series = \"X\" }"),
+        ];
+        let found = |policy| {
+            let options = Options::new(".").drafts(Some(policy));
+            check_site(Path::new("."), &files, &options)
+                .diagnostics()
+                .iter()
+                .map(|x| format!("{} {} {}", x.path().display(), x.rule(), x.severity()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            found(DraftPolicy::Check),
+            [
+                "a.md duplicate-title warning",
+                "b.md duplicate-title warning",
+                "c.md mixed-date-formats warning",
+                "d.md mixed-date-formats warning",
+            ]
+        );
+        assert_eq!(found(DraftPolicy::Skip), Vec::<String>::new());
+        assert_eq!(
+            found(DraftPolicy::NoFail),
+            [
+                "a.md duplicate-title warning",
+                "b.md duplicate-title info",
+                "c.md mixed-date-formats info",
+                "d.md mixed-date-formats info",
+            ]
+        );
+        assert_eq!(
+            found(DraftPolicy::FixOnly),
+            [
+                "a.md duplicate-title warning",
+                "c.md mixed-date-formats warning",
+                "d.md mixed-date-formats warning",
+            ]
+        );
+    }
+}
@@ -0,0 +1,325 @@
+//! Checks the images and other local files referenced in the content
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::{
+    Options, Report,
+    processing::log_diagnostic,
+    rule::{Rule, Severity},
+};
+
+use super::{SiteFile, lines_outside_code, strip_inline_code};
+
+/// Extensions of the files that are treated as images
+const IMAGE_EXTENSIONS: [&str; 9] = [
+    "png", "jpg", "jpeg", "gif", "webp", "avif", "svg", "bmp", "ico",
+];
+
+/// Extensions of links that go to pages rather than files
+const PAGE_EXTENSIONS: [&str; 3] = ["md", "html", "htm"];
+
+/// Matches markdown images (`![alt](src "title")`)
+static IMAGE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"!\[([^\]]*)\]\(\s*<?([^\s)>]+)>?(?:\s+[^)]*)?\)").unwrap());
+
+/// Matches markdown links that are not images (`[text](target)`)
+static LINK_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:^|[^!])\[[^\]]*\]\(\s*<?([^\s)>]+)").unwrap());
+
+/// Matches html image tags
+static IMG_TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<img\s[^>]*>").unwrap());
+
+/// Matches shortcode calls (`{{ name(args) }}` and `{% name(args) %}`)
+static SHORTCODE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{[{%]-?\s*\w+\(([^)]*)\)\s*-?[%}]\}").unwrap());
+
+/// Matches `key="value"` (or single quotes) in html attributes and shortcode
+/// arguments
+static ARG_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(\w+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap());
+
+/// A reference to a local file found in the content
+#[derive(Debug, PartialEq, Eq)]
+struct AssetRef<'a> {
+    /// Index of the line (of the content) it is on
+    line: usize,
+    src: &'a str,
+    /// The alt text for images (None if not an image)
+    alt: Option<&'a str>,
+}
+
+/// Reports references to local files that do not exist, images without alt
+/// text and images above the size limit
+pub(crate) fn check_assets(root_path: &Path, files: &[SiteFile], options: &Options) -> Report {
+    let mut result = Report::new();
+    let static_root = root_path.join("static");
+    let content_root = root_path.join("content");
+    let max_size_kb = options.get_max_image_size_kb();
+
+    for file in files {
+        let page_dir = file.path.parent().unwrap_or(root_path);
+        // zola only serves the files next to page bundles and sections
+        let assets_dir = has_colocated_assets(&file.path).then_some(page_dir);
+        for asset in find_assets(&file.content) {
+            let line = file.content_start_line + asset.line;
+            let src = asset.src;
+            let mut add = |rule: Rule, message: String| {
+                let severity = file.severity(rule, options);
+                if severity == Severity::Off {
+                    return;
+                }
+                log_diagnostic(
+                    severity,
+                    rule,
+                    &format!("{message}. In file at: {:?}", file.path),
+                );
+                result.add_diagnostic(&file.path, rule, severity, message, src);
+            };
+            if asset.alt.is_some_and(|alt| alt.trim().is_empty()) {
+                add(
+                    Rule::ImageMissingAlt,
+                    format!("image {src:?} on line {line} has no alt text"),
+                );
+            }
+            let Some(path) = resolve(src, assets_dir, &content_root, &static_root) else {
+                let message = if assets_dir.is_none() && page_dir.join(src).is_file() {
+                    format!(
+                        "{src:?} on line {line} is next to the page but only page bundles (`index.md`) and sections serve the files next to them"
+                    )
+                } else {
+                    format!("{src:?} on line {line} was not found next to the page or in `static/`")
+                };
+                add(Rule::MissingAsset, message);
+                continue;
+            };
+            if asset.alt.is_none() {
+                continue;
+            }
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            let size_kb = metadata.len().div_ceil(1024);
+            if size_kb > max_size_kb {
+                add(
+                    Rule::ImageTooLarge,
+                    format!(
+                        "image {src:?} on line {line} is {size_kb} KB which is more than the limit of {max_size_kb} KB"
+                    ),
+                );
+            }
+        }
+    }
+    result
+}
+
+/// The file `src` refers to (None if it does not exist)
+///
+/// Absolute paths are looked up in `static/` (and `content/` for files in
+/// other page bundles), relative paths in `assets_dir` (the folder of a page
+/// bundle or section) and then in `static/`
+fn resolve(
+    src: &str,
+    assets_dir: Option<&Path>,
+    content_root: &Path,
+    static_root: &Path,
+) -> Option<PathBuf> {
+    let candidates = match src.strip_prefix('/') {
+        Some(src) => vec![static_root.join(src), content_root.join(src)],
+        None => assets_dir
+            .map(|dir| dir.join(src))
+            .into_iter()
+            .chain([static_root.join(src)])
+            .collect(),
+    };
+    candidates.into_iter().find(|x| x.is_file())
+}
+
+/// True if the file is a page bundle (`index.md`) or section (`_index.md`),
+/// including translations like `index.fr.md`, as only their folders are
+/// served with them
+fn has_colocated_assets(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(|x| x.to_str())
+        .and_then(|stem| stem.split('.').next())
+        .is_some_and(|name| name == "index" || name == "_index")
+}
+
+/// Finds the references to local files outside of code
+///
+/// Images are found in markdown, html `img` tags and shortcode arguments
+/// (`src`, `path` or `file`). Links are included only if they have the
+/// extension of a file that is not a page.
+fn find_assets(content: &str) -> Vec<AssetRef<'_>> {
+    let mut result = Vec::new();
+    for (i, line) in lines_outside_code(content) {
+        // Matches are on the line with inline code blanked out but the text is
+        // taken from the original line (the positions are the same)
+        let searched = strip_inline_code(line);
+        let text = |m: regex::Match| &line[m.range()];
+        for caps in IMAGE_RE.captures_iter(&searched) {
+            let src = text(caps.get(2).unwrap());
+            if let Some(src) = local_path(src) {
+                let alt = Some(text(caps.get(1).unwrap()));
+                result.push(AssetRef { line: i, src, alt });
+            }
+        }
+        for caps in LINK_RE.captures_iter(&searched) {
+            let src = text(caps.get(1).unwrap());
+            if let Some(src) = local_path(src).filter(|x| is_file_link(x)) {
+                result.push(AssetRef {
+                    line: i,
+                    src,
+                    alt: None,
+                });
+            }
+        }
+        let tags = IMG_TAG_RE.find_iter(&searched).map(|m| (m.range(), true));
+        let shortcodes = SHORTCODE_RE
+            .captures_iter(&searched)
+            .map(|caps| (caps.get(1).unwrap().range(), false));
+        for (range, is_img_tag) in tags.chain(shortcodes) {
+            let mut src = None;
+            let mut alt = None;
+            for caps in ARG_RE.captures_iter(&line[range]) {
+                let value = caps.get(2).or_else(|| caps.get(3)).unwrap().as_str();
+                match &caps[1] {
+                    "src" | "path" | "file" => src = Some(value),
+                    "alt" => alt = Some(value),
+                    _ => {}
+                }
+            }
+            let Some(src) = src.and_then(local_path) else {
+                continue;
+            };
+            let alt = (is_img_tag || is_image(src)).then_some(alt.unwrap_or(""));
+            result.push(AssetRef { line: i, src, alt });
+        }
+    }
+    result
+}
+
+/// `src` without any query or fragment if it refers to a local file
+fn local_path(src: &str) -> Option<&str> {
+    let is_external = src.contains("://")
+        || src.starts_with("//")
+        || src.starts_with('#')
+        || src.starts_with("@/")
+        || ["mailto:", "tel:", "data:"]
+            .iter()
+            .any(|x| src.starts_with(x));
+    if is_external {
+        return None;
+    }
+    let src = src.split(['?', '#']).next().unwrap_or(src);
+    (!src.is_empty()).then_some(src)
+}
+
+fn extension(src: &str) -> Option<String> {
+    Path::new(src)
+        .extension()
+        .map(|x| x.to_string_lossy().to_lowercase())
+}
+
+fn is_image(src: &str) -> bool {
+    extension(src).is_some_and(|x| IMAGE_EXTENSIONS.contains(&x.as_str()))
+}
+
+/// Links with an extension are to files unless the extension is for a page
+fn is_file_link(src: &str) -> bool {
+    extension(src).is_some_and(|x| !PAGE_EXTENSIONS.contains(&x.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::site::tests::site_file;
+
+    #[test]
+    fn find() {
+        let content = r#"![A cat](cat.png "Cat") ![](/img/dog.jpg) [pdf](files/a.pdf)
+[page](../other/) [site](https://example.com/a.png) `![x](code.png)`
+<img src="b.webp" alt="B"> {{ image(src="c.png") }} {{ youtube(id="x") }}
+"#;
+        let assets = find_assets(content);
+        let summary: Vec<_> = assets.iter().map(|x| (x.line, x.src, x.alt)).collect();
+        assert_eq!(
+            summary,
+            [
+                (0, "cat.png", Some("A cat")),
+                (0, "/img/dog.jpg", Some("")),
+                (0, "files/a.pdf", None),
+                (2, "b.webp", Some("B")),
+                (2, "c.png", Some("")),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_and_large_images() {
+        let dir = tempfile::tempdir().unwrap();
+        let page_dir = dir.path().join("content/blog/post");
+        fs::create_dir_all(&page_dir).unwrap();
+        fs::create_dir(dir.path().join("static")).unwrap();
+        fs::write(page_dir.join("small.png"), [0; 10]).unwrap();
+        fs::write(dir.path().join("static/large.png"), vec![0; 2048]).unwrap();
+        let file = SiteFile {
+            content: "![Small](small.png) ![](/large.png)\n![Gone](gone.png)\n".to_string(),
+            ..site_file(page_dir.join("index.md"), "")
+        };
+        let options = Options::new(dir.path()).max_image_size_kb(1);
+        let report = check_assets(dir.path(), &[file], &options);
+        let found: Vec<_> = report
+            .diagnostics()
+            .iter()
+            .map(|x| (x.rule(), x.message()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    Rule::ImageMissingAlt,
+                    "image \"/large.png\" on line 1 has no alt text"
+                ),
+                (
+                    Rule::ImageTooLarge,
+                    "image \"/large.png\" on line 1 is 2 KB which is more than the limit of 1 KB"
+                ),
+                (
+                    Rule::MissingAsset,
+                    "\"gone.png\" on line 2 was not found next to the page or in `static/`"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn assets_next_to_pages_that_are_not_bundles() {
+        let dir = tempfile::tempdir().unwrap();
+        let blog = dir.path().join("content/blog");
+        fs::create_dir_all(&blog).unwrap();
+        fs::write(blog.join("img.png"), [0; 10]).unwrap();
+        let files = ["post.md", "_index.md", "index.fr.md"].map(|name| SiteFile {
+            content: "![Img](img.png)\n".to_string(),
+            ..site_file(blog.join(name), "")
+        });
+        let report = check_assets(dir.path(), &files, &Options::new(dir.path()));
+        let found: Vec<_> = report
+            .diagnostics()
+            .iter()
+            .map(|x| (x.path().file_name().unwrap().to_str().unwrap(), x.message()))
+            .collect();
+        assert_eq!(
+            found,
+            [(
+                "post.md",
+                "\"img.png\" on line 1 is next to the page but only page bundles (`index.md`) and sections serve the files next to them"
+            )]
+        );
+    }
+}
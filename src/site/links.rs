@@ -0,0 +1,306 @@
+//! Checks the internal links (`@/`) between content files
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::{
+    FileData, Options, Report,
+    processing::{is_draft, log_diagnostic},
+    rule::{Rule, Severity},
+};
+
+use super::{
+    SiteFile, lines_outside_code, strip_inline_code,
+    urls::{UrlConfig, slugify},
+};
+
+/// Matches the target of inline links (`[x](@/a.md#b)`) and link reference
+/// definitions (`[x]: @/a.md#b`)
+static LINK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?:\]\(\s*<?|^\s{0,3}\[[^\]]+\]:\s*<?)@/([^\s)#>"]+)(?:#([^\s)>"]*))?"#).unwrap()
+});
+
+/// Matches ATX headings (`## Heading {#id}`)
+static HEADING_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s{0,3}#{1,6}(?:\s+(.*?))?(?:\s+#+)?\s*$").unwrap());
+
+/// Matches an explicit id at the end of a heading (`{#id}`)
+static HEADING_ID_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(.*?)\s*\{#([^}\s]+)\}$").unwrap());
+
+/// Matches inline links and images in headings to keep only their text
+static INLINE_LINK_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"!?\[([^\]]*)\]\([^)]*\)").unwrap());
+
+/// Reports `@/` links to files that do not exist and links with a fragment
+/// that no heading in the target file produces
+pub(crate) fn check_links(root_path: &Path, files: &[SiteFile], options: &Options) -> Report {
+    let mut result = Report::new();
+    let content_root = root_path.join("content");
+    let config = UrlConfig::load(root_path);
+    let contents: HashMap<&Path, &str> = files
+        .iter()
+        .map(|file| (file.path.as_path(), file.content.as_str()))
+        .collect();
+    let drafts: HashMap<&Path, bool> = files
+        .iter()
+        .map(|file| (file.path.as_path(), file.is_draft))
+        .collect();
+    let mut anchors_cache: HashMap<PathBuf, Option<BTreeSet<String>>> = HashMap::new();
+    let mut drafts_cache: HashMap<PathBuf, bool> = HashMap::new();
+
+    for file in files {
+        for (line, target, fragment) in find_links(&file.content) {
+            let line = file.content_start_line + line;
+            let link = match fragment {
+                Some(fragment) => format!("@/{target}#{fragment}"),
+                None => format!("@/{target}"),
+            };
+            let target_path = content_root.join(target);
+            let (rule, message) = if !target_path.is_file() {
+                (
+                    Rule::BrokenLink,
+                    format!("link to {link:?} on line {line} points to a file that does not exist"),
+                )
+            } else if !file.is_draft
+                && *drafts_cache.entry(target_path.clone()).or_insert_with(|| {
+                    match drafts.get(target_path.as_path()) {
+                        Some(is_draft) => *is_draft,
+                        None => is_draft_on_disk(&target_path),
+                    }
+                })
+            {
+                (
+                    Rule::LinkToDraft,
+                    format!(
+                        "link to {link:?} on line {line} points to a draft (Drafts are not built)"
+                    ),
+                )
+            } else if let Some(fragment) = fragment.filter(|x| !x.is_empty()) {
+                let anchors = anchors_cache.entry(target_path.clone()).or_insert_with(|| {
+                    match contents.get(target_path.as_path()) {
+                        Some(content) => Some(find_anchors(content, &config)),
+                        None => read_content(&target_path)
+                            .map(|content| find_anchors(&content, &config)),
+                    }
+                });
+                match anchors {
+                    Some(anchors) if !anchors.contains(fragment) => (
+                        Rule::BrokenAnchor,
+                        format!(
+                            "link to {link:?} on line {line} points to an anchor that no heading in the target produces"
+                        ),
+                    ),
+                    _ => continue,
+                }
+            } else {
+                continue;
+            };
+            let severity = file.severity(rule, options);
+            if severity == Severity::Off {
+                continue;
+            }
+            log_diagnostic(
+                severity,
+                rule,
+                &format!("{message}. In file at: {:?}", file.path),
+            );
+            result.add_diagnostic(&file.path, rule, severity, message, &link);
+        }
+    }
+    result
+}
+
+/// The content of a file that was not processed (None if it cannot be read,
+/// as the anchors cannot be checked)
+fn read_content(path: &Path) -> Option<String> {
+    let text = fs::read_to_string(path).ok()?;
+    let data = FileData::new_from_str(path, &text).ok()?;
+    Some(data.content().to_string())
+}
+
+/// True if the file that was not processed is a draft (False if it cannot be
+/// read)
+fn is_draft_on_disk(path: &Path) -> bool {
+    fs::read_to_string(path).ok().is_some_and(|text| {
+        FileData::new_from_str(path, &text)
+            .and_then(|data| data.front_matter_as_toml())
+            .is_ok_and(|doc| is_draft(&doc))
+    })
+}
+
+/// Finds the `@/` links outside of code with the index of the line (of the
+/// content) they are on, the path of the target and the fragment if there is
+/// one
+fn find_links(content: &str) -> Vec<(usize, &str, Option<&str>)> {
+    let mut result = Vec::new();
+    for (i, line) in lines_outside_code(content) {
+        let line_without_code = strip_inline_code(line);
+        for caps in LINK_RE.captures_iter(&line_without_code) {
+            // Return slices of the original line (the captures are the same
+            // text as inline code is only blanked out)
+            let target = caps.get(1).unwrap().range();
+            let fragment = caps.get(2).map(|x| &line[x.range()]);
+            result.push((i, &line[target], fragment));
+        }
+    }
+    result
+}
+
+/// The anchor ids zola generates for the headings in `content`
+///
+/// Headings with an explicit id (`{#id}`) use it as is, otherwise the text of
+/// the heading is slugified. Repeated ids get a suffix of `-1`, `-2`, etc.
+fn find_anchors(content: &str, config: &UrlConfig) -> BTreeSet<String> {
+    let mut result = BTreeSet::new();
+    let mut previous: Option<&str> = None;
+    for (_, line) in lines_outside_code(content) {
+        let heading = if let Some(caps) = HEADING_RE.captures(line) {
+            Some(caps.get(1).map_or("", |x| x.as_str()))
+        } else if is_setext_underline(line) {
+            previous.filter(|x| !x.trim().is_empty() && !HEADING_RE.is_match(x))
+        } else {
+            None
+        };
+        previous = Some(line);
+        let Some(heading) = heading else {
+            continue;
+        };
+        let id = match HEADING_ID_RE.captures(heading.trim()) {
+            Some(caps) => caps[2].to_string(),
+            None => {
+                let text = INLINE_LINK_RE.replace_all(heading, "$1");
+                let text: String = text.chars().filter(|c| !"*_`~".contains(*c)).collect();
+                slugify(&text, config.slugify_anchors)
+            }
+        };
+        let mut unique = id.clone();
+        let mut suffix = 1;
+        while result.contains(&unique) {
+            unique = format!("{id}-{suffix}");
+            suffix += 1;
+        }
+        result.insert(unique);
+    }
+    result
+}
+
+/// A line of `=` or `-` that turns the line above it into a heading
+fn is_setext_underline(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && (line.chars().all(|c| c == '=') || line.chars().all(|c| c == '-'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::site::tests::site_file;
+
+    #[test]
+    fn links_and_anchors() {
+        let content = "\
+# Intro
+See [a](@/rust/a.md#setup) and `[b](@/rust/b.md)`
+
+```md
+[c](@/rust/c.md)
+```
+[d]: @/rust/d.md
+
+## Setup
+## Setup
+## Custom {#my-id}
+Other *Heading*
+---
+";
+        let links = find_links(content);
+        assert_eq!(
+            links,
+            [(1, "rust/a.md", Some("setup")), (6, "rust/d.md", None)]
+        );
+        let anchors = find_anchors(content, &UrlConfig::default());
+        assert_eq!(
+            anchors,
+            BTreeSet::from(
+                ["intro", "setup", "setup-1", "my-id", "other-heading"].map(String::from)
+            )
+        );
+    }
+
+    #[test]
+    fn links_to_drafts() {
+        let dir = tempfile::tempdir().unwrap();
+        let content_root = dir.path().join("content");
+        fs::create_dir(&content_root).unwrap();
+        for (name, text) in [
+            ("a.md", "+++\n+++\n"),
+            ("b.md", "+++\ndraft = true\n+++\n"),
+            ("c.md", "+++\ndraft = true\n+++\n"),
+            ("d.md", "+++\ndraft = true\n+++\n"),
+        ] {
+            fs::write(content_root.join(name), text).unwrap();
+        }
+        let links = "[b](@/b.md) [c](@/c.md) [a](@/a.md)\n";
+        // c.md is not in the files so it is read from disk
+        let files = [
+            SiteFile {
+                content: links.to_string(),
+                ..site_file(content_root.join("a.md"), "")
+            },
+            SiteFile {
+                is_draft: true,
+                ..site_file(content_root.join("b.md"), "draft = true")
+            },
+            SiteFile {
+                content: links.to_string(),
+                is_draft: true,
+                ..site_file(content_root.join("d.md"), "draft = true")
+            },
+        ];
+        let report = check_links(dir.path(), &files, &Options::new(dir.path()));
+        let found: Vec<_> = report
+            .diagnostics()
+            .iter()
+            .map(|x| (x.path().file_name().unwrap().to_str().unwrap(), x.rule()))
+            .collect();
+        assert_eq!(
+            found,
+            [("a.md", Rule::LinkToDraft), ("a.md", Rule::LinkToDraft)]
+        );
+        assert!(report.diagnostics()[1].message().contains("\"@/c.md\""));
+    }
+
+    #[test]
+    fn broken_links_and_anchors() {
+        let dir = tempfile::tempdir().unwrap();
+        let content_root = dir.path().join("content");
+        fs::create_dir(&content_root).unwrap();
+        fs::write(content_root.join("a.md"), "+++\n+++\n").unwrap();
+        fs::write(content_root.join("b.md"), "+++\n+++\n## Setup\n").unwrap();
+        let links = "[b](@/b.md#setup) [b](@/b.md#missing)\n[x](@/x.md)\n";
+        let files = [
+            SiteFile {
+                content: links.to_string(),
+                ..site_file(content_root.join("a.md"), "")
+            },
+            SiteFile {
+                content: links.to_string(),
+                suppressed: BTreeSet::from([Rule::BrokenLink, Rule::BrokenAnchor]),
+                ..site_file(content_root.join("c.md"), "")
+            },
+        ];
+        let report = check_links(dir.path(), &files, &Options::new(dir.path()));
+        let found: Vec<_> = report.diagnostics().iter().map(|x| x.message()).collect();
+        assert_eq!(
+            found,
+            [
+                "link to \"@/b.md#missing\" on line 1 points to an anchor that no heading in the target produces",
+                "link to \"@/x.md\" on line 2 points to a file that does not exist",
+            ]
+        );
+    }
+}
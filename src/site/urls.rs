@@ -0,0 +1,341 @@
+//! Works out the URLs zola generates for each file to find collisions
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use toml_edit::DocumentMut;
+
+use crate::{
+    Options, Report,
+    processing::log_diagnostic,
+    rule::{Rule, Severity},
+};
+
+use super::{SiteFile, relative};
+
+/// Matches a date at the start of a file name followed by the slug (Same as
+/// zola)
+static DATE_PREFIX_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\d{4}-(?:0[1-9]|1[012])-(?:0[1-9]|[12]\d|3[01])(?:T(?:[01]\d|2[0-3]):[0-5]\d:(?:[0-5]\d|60)(?:\.\d+)?(?:Z|[+-](?:[01]\d|2[0-3]):[0-5]\d))?\s?[_-](.+)$",
+    )
+    .unwrap()
+});
+
+/// Settings from the zola config that affect URLs
+#[derive(Debug, Default)]
+pub(super) struct UrlConfig {
+    /// Codes of the languages other than the default
+    languages: BTreeSet<String>,
+    /// Value of `slugify.paths`
+    slugify: SlugifyStrategy,
+    /// Value of `slugify.anchors`
+    pub(super) slugify_anchors: SlugifyStrategy,
+}
+
+/// Values for the `slugify` settings in the zola config
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(super) enum SlugifyStrategy {
+    #[default]
+    On,
+    Safe,
+    Off,
+}
+
+/// Where a URL came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UrlKind {
+    Page,
+    Section,
+    Alias,
+}
+
+impl std::fmt::Display for UrlKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UrlKind::Page => write!(f, "page"),
+            UrlKind::Section => write!(f, "section"),
+            UrlKind::Alias => write!(f, "alias"),
+        }
+    }
+}
+
+impl UrlConfig {
+    /// Reads the settings from the `config.toml` of the site at `root_path`
+    ///
+    /// Problems reading the config are already reported when it is validated
+    /// so the defaults are used instead
+    pub(super) fn load(root_path: &Path) -> Self {
+        let mut result = Self::default();
+        let Some(doc) = fs::read_to_string(root_path.join("config.toml"))
+            .ok()
+            .and_then(|text| text.parse::<DocumentMut>().ok())
+        else {
+            return result;
+        };
+        if let Some(languages) = doc.get("languages").and_then(|x| x.as_table_like()) {
+            result.languages = languages.iter().map(|(key, _)| key.to_string()).collect();
+        }
+        let strategy = |key: &str| match doc
+            .get("slugify")
+            .and_then(|x| x.get(key))
+            .and_then(|x| x.as_str())
+        {
+            Some("safe") => SlugifyStrategy::Safe,
+            Some("off") => SlugifyStrategy::Off,
+            _ => SlugifyStrategy::On,
+        };
+        result.slugify = strategy("paths");
+        result.slugify_anchors = strategy("anchors");
+        result
+    }
+}
+
+/// Reports files that produce the same URL as another file and aliases that
+/// point at the URL of a page, section or another alias
+pub(crate) fn check_urls(root_path: &Path, files: &[SiteFile], options: &Options) -> Report {
+    let mut result = Report::new();
+    let config = UrlConfig::load(root_path);
+    let content_root = root_path.join("content");
+
+    let mut urls: BTreeMap<String, Vec<(&SiteFile, UrlKind)>> = BTreeMap::new();
+    for file in files {
+        let Some((url, kind)) = file_url(&content_root, file, &config) else {
+            continue;
+        };
+        urls.entry(url).or_default().push((file, kind));
+        let aliases = file
+            .front_matter
+            .get("aliases")
+            .and_then(|x| x.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|x| x.as_str());
+        for alias in aliases {
+            urls.entry(normalize_url(alias))
+                .or_default()
+                .push((file, UrlKind::Alias));
+        }
+    }
+
+    for (url, users) in urls.iter().filter(|(_, users)| users.len() > 1) {
+        for (i, (file, kind)) in users.iter().enumerate() {
+            let rule = if *kind == UrlKind::Alias
+                || users.iter().any(|(_, kind)| *kind == UrlKind::Alias)
+            {
+                Rule::AliasCollision
+            } else {
+                Rule::UrlCollision
+            };
+            let severity = file.severity(rule, options);
+            if severity == Severity::Off {
+                continue;
+            }
+            let others: Vec<_> = users
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| i != *j)
+                .map(|(_, (other, kind))| format!("{} ({kind})", relative(root_path, &other.path)))
+                .collect();
+            let message = format!(
+                "{kind} URL {url:?} is also generated by: {}",
+                others.join(", ")
+            );
+            log_diagnostic(
+                severity,
+                rule,
+                &format!("{message}. In file at: {:?}", file.path),
+            );
+            result.add_diagnostic(&file.path, rule, severity, message, url);
+        }
+    }
+    result
+}
+
+/// The URL (path only) zola generates for `file` (None if it is not under
+/// `content_root`)
+fn file_url(content_root: &Path, file: &SiteFile, config: &UrlConfig) -> Option<(String, UrlKind)> {
+    let relative = file.path.strip_prefix(content_root).ok()?;
+    let file_name = relative.file_name()?.to_str()?;
+    let mut components: Vec<String> = relative
+        .parent()?
+        .components()
+        .map(|x| x.as_os_str().to_string_lossy().to_string())
+        .collect();
+
+    // Files for other languages are named like `page.fr.md`
+    let stem = file_name.strip_suffix(".md")?;
+    let (stem, language) = match stem.rsplit_once('.') {
+        Some((stem, lang)) if config.languages.contains(lang) => (stem, Some(lang)),
+        _ => (stem, None),
+    };
+    let prefix = language.map_or(String::new(), |lang| format!("/{lang}"));
+
+    if stem == "_index" {
+        let path = components.join("/");
+        return Some((normalize_url(&format!("{prefix}/{path}")), UrlKind::Section));
+    }
+
+    if let Some(path) = file.front_matter.get("path").and_then(|x| x.as_str()) {
+        return Some((
+            normalize_url(&format!("{prefix}/{}", path.trim())),
+            UrlKind::Page,
+        ));
+    }
+
+    // For a page bundle (`index.md`) the folder provides the slug
+    let file_slug = if stem == "index" {
+        components.pop()?
+    } else {
+        stem.to_string()
+    };
+    let slug = match file.front_matter.get("slug").and_then(|x| x.as_str()) {
+        Some(slug) => slugify(slug.trim(), config.slugify),
+        None => {
+            let slug = DATE_PREFIX_RE
+                .captures(&file_slug)
+                .map_or(file_slug.as_str(), |caps| caps.get(1).unwrap().as_str());
+            slugify(slug, config.slugify)
+        }
+    };
+    components.push(slug);
+    Some((
+        normalize_url(&format!("{prefix}/{}", components.join("/"))),
+        UrlKind::Page,
+    ))
+}
+
+/// Makes URLs comparable by ensuring they start and end with a single `/`
+fn normalize_url(url: &str) -> String {
+    let url = url.trim().trim_matches('/');
+    if url.is_empty() {
+        "/".to_string()
+    } else {
+        format!("/{url}/")
+    }
+}
+
+/// Approximates zola's slugify of paths and anchors
+///
+/// `On` lower cases and replaces everything other than letters and digits with
+/// `-`, `Safe` only removes characters that are not safe in file names and
+/// `Off` leaves the value as is
+///
+/// Unlike zola (which uses the `slug` crate) `On` does not transliterate to
+/// ASCII: `Café` becomes `café` rather than `cafe`. The URLs of pages with
+/// letters outside of ASCII in their slug can differ from zola's so collisions
+/// between them and the ASCII spelling are not found.
+pub(super) fn slugify(value: &str, setting: SlugifyStrategy) -> String {
+    match setting {
+        SlugifyStrategy::Off => value.to_string(),
+        SlugifyStrategy::Safe => value
+            .chars()
+            .filter(|c| {
+                !matches!(
+                    c,
+                    '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' | '#'
+                )
+            })
+            .collect::<String>()
+            .trim()
+            .to_string(),
+        SlugifyStrategy::On => value
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|x| !x.is_empty())
+            .collect::<Vec<_>>()
+            .join("-"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::site::tests::site_file;
+
+    fn url(path: &str, front_matter: &str) -> String {
+        let file = site_file(Path::new("content").join(path), front_matter);
+        let config = UrlConfig {
+            languages: BTreeSet::from(["fr".to_string()]),
+            ..Default::default()
+        };
+        file_url(Path::new("content"), &file, &config).unwrap().0
+    }
+
+    #[test]
+    fn urls() {
+        assert_eq!(url("_index.md", ""), "/");
+        assert_eq!(url("blog/_index.md", ""), "/blog/");
+        assert_eq!(url("blog/My Post.md", ""), "/blog/my-post/");
+        assert_eq!(url("blog/2023-01-02-first.md", ""), "/blog/first/");
+        assert_eq!(
+            url("blog/2023-01-02_first.md", "slug = \"One\""),
+            "/blog/one/"
+        );
+        assert_eq!(
+            url("blog/2023-01-02T10:00:00Z-first.md", ""),
+            "/blog/first/"
+        );
+        assert_eq!(
+            url("blog/2023-01-02T10:00:00.5+01:00_first.md", ""),
+            "/blog/first/"
+        );
+        assert_eq!(url("blog/2023-01-02 _first.md", ""), "/blog/first/");
+        assert_eq!(
+            url("blog/2023-01-02T10-first.md", ""),
+            "/blog/2023-01-02t10-first/"
+        );
+        assert_eq!(url("blog/bundle/index.md", ""), "/blog/bundle/");
+        assert_eq!(url("blog/post.fr.md", ""), "/fr/blog/post/");
+        assert_eq!(url("blog/post.md", "path = \"about\""), "/about/");
+    }
+
+    #[test]
+    fn slugify_on() {
+        let slug = |x| slugify(x, SlugifyStrategy::On);
+        assert_eq!(slug("My First  Post!"), "my-first-post");
+        assert_eq!(slug("--Rust & C++--"), "rust-c");
+        assert_eq!(slug("Part 2.1"), "part-2-1");
+        // zola transliterates these to "cafe" and "privet"
+        assert_eq!(slug("Café"), "café");
+        assert_eq!(slug("Привет"), "привет");
+    }
+
+    #[test]
+    fn collisions() {
+        let files = [
+            site_file("content/blog/_index.md", ""),
+            site_file("content/blog/post.md", ""),
+            site_file("content/blog/2024-01-01-post.md", ""),
+            site_file("content/about.md", "aliases = [\"/blog/\"]"),
+            SiteFile {
+                suppressed: BTreeSet::from([Rule::UrlCollision]),
+                ..site_file("content/other.md", "path = \"blog/post\"")
+            },
+        ];
+        let report = check_urls(Path::new(""), &files, &Options::new("."));
+        let found: Vec<_> = report
+            .diagnostics()
+            .iter()
+            .map(|x| (x.path().to_str().unwrap(), x.rule()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("content/blog/_index.md", Rule::AliasCollision),
+                ("content/about.md", Rule::AliasCollision),
+                ("content/blog/post.md", Rule::UrlCollision),
+                ("content/blog/2024-01-01-post.md", Rule::UrlCollision),
+            ]
+        );
+        assert_eq!(
+            report.diagnostics()[2].message(),
+            "page URL \"/blog/post/\" is also generated by: content/blog/2024-01-01-post.md (page), content/other.md (page)"
+        );
+    }
+}
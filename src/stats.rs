@@ -1,16 +1,62 @@
 //! Information Related to Run Statistics
 
-use std::{fmt::Display, ops::AddAssign};
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    ops::AddAssign,
+    path::{Path, PathBuf},
+};
 
-/// Tracks files changed (NB: Stops counting at 2^16 and saturates)
-#[derive(Debug, Default)]
+use serde::{Deserialize, Serialize};
+
+/// Tracks files changed (NB: Saturates, stops counting at `u64::MAX`)
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[must_use]
 pub struct Stats {
-    changed: u16,
-    not_changed: u16,
-    skipped: u16,
-    seo_warnings: u16,
-    errors: u16,
+    changed: u64,
+    not_changed: u64,
+    skipped: u64,
+    seo_warnings: u64,
+    errors: u64,
+    /// Words in the content of all pages processed
+    words: u64,
+    /// Files other than `index.md` in page bundles
+    bundle_assets: u64,
+    rules: RuleStats,
+    /// Keyed on the path of the section file (`_index.md`) so sections with
+    /// the same name are kept apart
+    sections: BTreeMap<PathBuf, SectionStats>,
+}
+
+/// Counts of how often each rule applied (NB: Saturates)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleStats {
+    /// Pages that had the section name added to their tags
+    pub tags_added: u64,
+    /// Pages that had their series set to the section name
+    pub series_set: u64,
+    /// Pages that had `updated` set from the git history
+    pub updated_set: u64,
+    /// Descriptions that were not found
+    pub description_missing: u64,
+    /// Descriptions shorter than the preferred range
+    pub description_too_short: u64,
+    /// Descriptions longer than the preferred range
+    pub description_too_long: u64,
+}
+
+/// Counts for the pages in a single section (NB: Saturates)
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionStats {
+    /// Name of the section (Its title or else its folder name)
+    pub name: String,
+    /// Pages in the section (Including pages in sub folders that are not
+    /// sections)
+    pub pages: u64,
+    /// Words in the content of the pages in the section
+    pub words: u64,
+    /// Rules that applied to pages in the section
+    pub rules: RuleStats,
 }
 
 impl Stats {
@@ -19,30 +65,53 @@ impl Stats {
     }
 
     /// Gets the current value of `changed`
-    pub fn changed(&self) -> u16 {
+    pub fn changed(&self) -> u64 {
         self.changed
     }
 
     /// Gets the current value of `not_changed`
-    pub fn not_changed(&self) -> u16 {
+    pub fn not_changed(&self) -> u64 {
         self.not_changed
     }
 
     /// Gets the current value of `skipped`
-    pub fn skipped(&self) -> u16 {
+    pub fn skipped(&self) -> u64 {
         self.skipped
     }
 
-    /// Gets the current value of `errors`
-    pub fn errors(&self) -> u16 {
+    /// Gets the current value of `errors` (Problems in the baseline are not
+    /// counted)
+    pub fn errors(&self) -> u64 {
         self.errors
     }
 
-    /// Gets the current value of `seo_failures`
-    pub fn seo_warnings(&self) -> u16 {
+    /// Gets the current value of `seo_warnings` (Problems in the baseline are
+    /// not counted)
+    pub fn seo_warnings(&self) -> u64 {
         self.seo_warnings
     }
 
+    /// Gets the number of words in the content of all pages processed
+    pub fn words(&self) -> u64 {
+        self.words
+    }
+
+    /// Gets the number of files other than `index.md` in page bundles
+    pub fn bundle_assets(&self) -> u64 {
+        self.bundle_assets
+    }
+
+    /// Gets the counts of how often each rule applied
+    pub fn rules(&self) -> &RuleStats {
+        &self.rules
+    }
+
+    /// Gets the counts for each section (Keyed on the path of the section
+    /// file)
+    pub fn sections(&self) -> &BTreeMap<PathBuf, SectionStats> {
+        &self.sections
+    }
+
     /// Increments `changed` by 1 (saturating if applicable)
     pub fn inc_changed(&mut self) {
         self.changed = self.changed.saturating_add(1);
@@ -63,10 +132,52 @@ impl Stats {
         self.errors = self.errors.saturating_add(1);
     }
 
+    /// Decrements `errors` by 1 (For problems found to be in the baseline)
+    pub(crate) fn dec_errors(&mut self) {
+        self.errors = self.errors.saturating_sub(1);
+    }
+
+    /// Decrements `seo_warnings` by 1 (For problems found to be in the
+    /// baseline)
+    pub(crate) fn dec_seo_warnings(&mut self) {
+        self.seo_warnings = self.seo_warnings.saturating_sub(1);
+    }
+
     /// Increments `seo_warnings` by 1 (saturating if applicable)
     pub fn inc_seo_warnings(&mut self) {
         self.seo_warnings = self.seo_warnings.saturating_add(1);
     }
+
+    /// Increments `bundle_assets` by 1 (saturating if applicable)
+    pub fn inc_bundle_assets(&mut self) {
+        self.bundle_assets = self.bundle_assets.saturating_add(1);
+    }
+
+    pub(crate) fn rules_mut(&mut self) -> &mut RuleStats {
+        &mut self.rules
+    }
+
+    /// Records a page with `words` words in its content. If the page is in a
+    /// section (given as the path of its section file and its name)
+    /// `page_rules` (the rules that applied to the page) are also added to
+    /// the section's counts.
+    pub(crate) fn add_page(
+        &mut self,
+        section: Option<(&Path, &str)>,
+        words: u64,
+        page_rules: RuleStats,
+    ) {
+        self.words = self.words.saturating_add(words);
+        if let Some((path, name)) = section {
+            let section = self.sections.entry(path.to_path_buf()).or_default();
+            if section.name.is_empty() {
+                section.name = name.to_string();
+            }
+            section.pages = section.pages.saturating_add(1);
+            section.words = section.words.saturating_add(words);
+            section.rules += page_rules;
+        }
+    }
 }
 
 impl AddAssign for Stats {
@@ -77,12 +188,58 @@ impl AddAssign for Stats {
             skipped,
             seo_warnings,
             errors,
+            words,
+            bundle_assets,
+            rules,
+            sections,
+        } = self;
+        *changed = changed.saturating_add(rhs.changed);
+        *not_changed = not_changed.saturating_add(rhs.not_changed);
+        *skipped = skipped.saturating_add(rhs.skipped);
+        *seo_warnings = seo_warnings.saturating_add(rhs.seo_warnings);
+        *errors = errors.saturating_add(rhs.errors);
+        *words = words.saturating_add(rhs.words);
+        *bundle_assets = bundle_assets.saturating_add(rhs.bundle_assets);
+        *rules += rhs.rules;
+        for (path, section_rhs) in rhs.sections {
+            *sections.entry(path).or_default() += section_rhs;
+        }
+    }
+}
+
+impl AddAssign for RuleStats {
+    fn add_assign(&mut self, rhs: Self) {
+        let Self {
+            tags_added,
+            series_set,
+            updated_set,
+            description_missing,
+            description_too_short,
+            description_too_long,
         } = self;
-        *changed += rhs.changed;
-        *not_changed += rhs.not_changed;
-        *skipped += rhs.skipped;
-        *seo_warnings += rhs.seo_warnings;
-        *errors += rhs.errors;
+        *tags_added = tags_added.saturating_add(rhs.tags_added);
+        *series_set = series_set.saturating_add(rhs.series_set);
+        *updated_set = updated_set.saturating_add(rhs.updated_set);
+        *description_missing = description_missing.saturating_add(rhs.description_missing);
+        *description_too_short = description_too_short.saturating_add(rhs.description_too_short);
+        *description_too_long = description_too_long.saturating_add(rhs.description_too_long);
+    }
+}
+
+impl AddAssign for SectionStats {
+    fn add_assign(&mut self, rhs: Self) {
+        let Self {
+            name,
+            pages,
+            words,
+            rules,
+        } = self;
+        if name.is_empty() {
+            *name = rhs.name;
+        }
+        *pages = pages.saturating_add(rhs.pages);
+        *words = words.saturating_add(rhs.words);
+        *rules += rhs.rules;
     }
 }
 
@@ -94,10 +251,84 @@ impl Display for Stats {
             skipped,
             seo_warnings,
             errors,
+            words,
+            bundle_assets,
+            rules,
+            sections,
         } = self;
         write!(
             f,
             "Changed: {changed}, Not Changed: {not_changed}, SEO Warnings: {seo_warnings}, Skipped: {skipped}, Errors: {errors}",
+        )?;
+        write!(
+            f,
+            "\n  Words: {words}, Bundle Assets: {bundle_assets}, {rules}"
+        )?;
+        for (path, section) in sections {
+            write!(
+                f,
+                "\n  Section {:?} ({}): Pages: {}, Words: {}, {}",
+                section.name,
+                path.display(),
+                section.pages,
+                section.words,
+                section.rules
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for RuleStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            tags_added,
+            series_set,
+            updated_set,
+            description_missing,
+            description_too_short,
+            description_too_long,
+        } = self;
+        write!(
+            f,
+            "Tags Added: {tags_added}, Series Set: {series_set}, Updated Set: {updated_set}, Description Missing: {description_missing}, Too Short: {description_too_short}, Too Long: {description_too_long}"
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections_with_the_same_name() {
+        let mut stats = Stats::new();
+        let rules = RuleStats {
+            series_set: 1,
+            ..Default::default()
+        };
+        stats.add_page(Some((Path::new("a/_index.md"), "Notes")), 10, rules);
+        let mut other = Stats::new();
+        other.add_page(Some((Path::new("b/_index.md"), "Notes")), 5, rules);
+        other.add_page(Some((Path::new("a/_index.md"), "Notes")), 1, rules);
+        stats += other;
+
+        let sections: Vec<_> = stats
+            .sections()
+            .iter()
+            .map(|(path, x)| (path.to_str().unwrap(), x.name.as_str(), x.pages, x.words))
+            .collect();
+        assert_eq!(
+            sections,
+            [
+                ("a/_index.md", "Notes", 2, 11),
+                ("b/_index.md", "Notes", 1, 5)
+            ]
+        );
+        assert_eq!(stats.words, 16);
+        assert_eq!(
+            stats.sections()[Path::new("a/_index.md")].rules.series_set,
+            2
+        );
+    }
+}
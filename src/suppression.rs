@@ -0,0 +1,145 @@
+//! Comments in the front matter that turn off a rule for a page or section
+//!
+//! ```toml
+//! # abridge-helper: ignore description-length reason="landing page"
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::{Error, rule::Rule};
+
+static DIRECTIVE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*#\s*abridge-helper\s*:(.*)$").unwrap());
+static IGNORE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"^\s*ignore\s+(\S+)(?:\s+reason\s*=\s*"([^"]*)")?\s*$"#).unwrap());
+
+/// A rule turned off by a comment in the front matter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suppression {
+    path: PathBuf,
+    line: usize,
+    rule: Rule,
+    reason: String,
+    is_section_wide: bool,
+}
+
+impl Suppression {
+    /// File the comment is in
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Line number of the comment in the front matter (Starting at 1)
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The rule that is turned off
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    /// Why the rule is turned off
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    /// True if the comment is in a section (`_index.md`) and applies to all
+    /// the pages in the section
+    pub fn is_section_wide(&self) -> bool {
+        self.is_section_wide
+    }
+}
+
+impl std::fmt::Display for Suppression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}] {:?} ({}): {}",
+            self.rule,
+            self.path,
+            if self.is_section_wide {
+                "section"
+            } else {
+                "page"
+            },
+            self.reason
+        )
+    }
+}
+
+/// Finds the suppression comments in `front_matter`
+///
+/// Comments that start like a suppression but are not valid (unknown rule or
+/// no reason) are returned as errors and do not suppress anything
+pub(crate) fn parse_suppressions(
+    path: &Path,
+    front_matter: &str,
+) -> (Vec<Suppression>, Vec<Error>) {
+    let is_section_wide = path.ends_with("_index.md");
+    let mut suppressions = Vec::new();
+    let mut errors = Vec::new();
+    // The front matter starts with the line break after the opening `+++`
+    for (line, text) in front_matter.lines().enumerate().skip(1) {
+        let Some(directive) = DIRECTIVE_RE.captures(text) else {
+            continue;
+        };
+        let invalid = |message: String| Error::InvalidSuppression {
+            path: path.to_path_buf(),
+            line,
+            message,
+        };
+        let Some(caps) = IGNORE_RE.captures(&directive[1]) else {
+            errors.push(invalid(
+                r#"expected `ignore <rule> reason="..."`"#.to_string(),
+            ));
+            continue;
+        };
+        let rule = match caps[1].parse::<Rule>() {
+            Ok(rule) => rule,
+            Err(e) => {
+                errors.push(invalid(e));
+                continue;
+            }
+        };
+        let reason = caps.get(2).map_or("", |x| x.as_str()).trim();
+        if reason.is_empty() {
+            errors.push(invalid(format!("a reason is required to ignore {rule}")));
+            continue;
+        }
+        suppressions.push(Suppression {
+            path: path.to_path_buf(),
+            line,
+            rule,
+            reason: reason.to_string(),
+            is_section_wide,
+        });
+    }
+    (suppressions, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let front_matter = r#"
+title = "Home"
+# abridge-helper: ignore description-length reason="landing page"
+#abridge-helper: ignore series-not-set
+# abridge-helper: ignore not-a-rule reason="x"
+# A normal comment
+"#;
+        let (suppressions, errors) = parse_suppressions(Path::new("page.md"), front_matter);
+        assert_eq!(suppressions.len(), 1);
+        assert_eq!(suppressions[0].rule(), Rule::DescriptionLength);
+        assert_eq!(suppressions[0].reason(), "landing page");
+        assert_eq!(suppressions[0].line(), 2);
+        assert!(!suppressions[0].is_section_wide());
+        assert_eq!(errors.len(), 2);
+    }
+}